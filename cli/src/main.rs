@@ -1,8 +1,16 @@
 use failure::Error;
 use std::fs;
+use std::io::{self, BufRead, Read, Write};
 use std::path::PathBuf;
+use std::process;
 use structopt::StructOpt;
-use tm::TuringMachine;
+use tm::{
+    analyze_termination, compile_bytecode, compile_native, execute_bytecode, parse_program,
+    Breakpoint, Outcome, RunUntil, StackMachine, StepOutcome, Stepper, Termination, TuringMachine,
+    Validate, ValidationOptions,
+};
+
+mod test_runner;
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -19,6 +27,18 @@ enum Opt {
         /// The input to pass to the machine for execution
         #[structopt(long = "tape", short = "t")]
         tape_input: String,
+
+        /// Maximum number of stack-machine instructions to execute before
+        /// aborting, for running untrusted machine definitions that might
+        /// not halt. Unbounded if omitted.
+        #[structopt(long = "max-steps")]
+        max_steps: Option<u64>,
+
+        /// Maximum stack depth to allow before aborting, for running
+        /// untrusted machine definitions that might exhaust memory.
+        /// Unbounded if omitted.
+        #[structopt(long = "max-stack-depth")]
+        max_stack_depth: Option<usize>,
     },
     #[structopt(name = "compile")]
     Compile {
@@ -29,12 +49,119 @@ enum Opt {
         /// The file to output Rocketlang code to
         #[structopt(parse(from_os_str), long = "output", short = "o")]
         output_file: PathBuf,
+
+        /// Statically check whether the machine can be proven to never
+        /// halt, and warn (without aborting the compile) if so
+        #[structopt(long = "check-termination")]
+        check_termination: bool,
+    },
+    #[structopt(name = "compile-native")]
+    CompileNative {
+        /// The file defining the Turing machine to run
+        #[structopt(parse(from_os_str), long = "input", short = "i")]
+        input_file: PathBuf,
+
+        /// The file to output generated C source to
+        #[structopt(parse(from_os_str), long = "output", short = "o")]
+        output_file: PathBuf,
+
+        /// Statically check whether the machine can be proven to never
+        /// halt, and warn (without aborting the compile) if so
+        #[structopt(long = "check-termination")]
+        check_termination: bool,
+    },
+    #[structopt(name = "exec-rocketlang")]
+    ExecRocketlang {
+        /// The Rocketlang source file to parse and run
+        #[structopt(parse(from_os_str), long = "input", short = "i")]
+        input_file: PathBuf,
+
+        /// The input to pass to the machine for execution
+        #[structopt(long = "tape", short = "t")]
+        tape_input: String,
+    },
+    #[structopt(name = "compile-bytecode")]
+    CompileBytecode {
+        /// The file defining the Turing machine to run
+        #[structopt(parse(from_os_str), long = "input", short = "i")]
+        input_file: PathBuf,
+
+        /// The file to output bytecode assembly to
+        #[structopt(parse(from_os_str), long = "output", short = "o")]
+        output_file: PathBuf,
+
+        /// Statically check whether the machine can be proven to never
+        /// halt, and warn (without aborting the compile) if so
+        #[structopt(long = "check-termination")]
+        check_termination: bool,
+    },
+    #[structopt(name = "exec-bytecode")]
+    ExecBytecode {
+        /// The bytecode assembly file to parse and run, or stdin if omitted
+        #[structopt(parse(from_os_str), long = "input", short = "i")]
+        input_file: Option<PathBuf>,
+
+        /// The input to pass to the machine for execution
+        #[structopt(long = "tape", short = "t")]
+        tape_input: String,
+    },
+    #[structopt(name = "debug")]
+    Debug {
+        /// The file defining the Turing machine to debug
+        #[structopt(parse(from_os_str), long = "input", short = "i")]
+        input_file: PathBuf,
+
+        /// The input to pass to the machine for execution
+        #[structopt(long = "tape", short = "t")]
+        tape_input: String,
+
+        /// Maximum number of stack-machine instructions to execute before
+        /// aborting, for debugging untrusted machine definitions that might
+        /// not halt. Unbounded if omitted.
+        #[structopt(long = "max-steps")]
+        max_steps: Option<u64>,
+
+        /// Maximum stack depth to allow before aborting, for debugging
+        /// untrusted machine definitions that might exhaust memory.
+        /// Unbounded if omitted.
+        #[structopt(long = "max-stack-depth")]
+        max_stack_depth: Option<usize>,
+    },
+    #[structopt(name = "test")]
+    Test {
+        /// The JSON manifest listing test cases, or a directory containing
+        /// a `manifest.json`
+        #[structopt(parse(from_os_str))]
+        manifest: PathBuf,
     },
 }
 
 fn tm_from_file(path: &PathBuf) -> Result<TuringMachine, Error> {
     let contents = fs::read_to_string(path)?;
-    TuringMachine::from_json(&contents)
+    TuringMachine::new(parse_program(&contents)?)
+}
+
+/// Same as `tm_from_file`, but bounds the machine with an execution budget
+/// (see `TuringMachine::new_with_limits`), for running/debugging untrusted
+/// machine definitions that might not halt or might exhaust memory.
+fn tm_from_file_with_limits(
+    path: &PathBuf,
+    max_steps: Option<u64>,
+    max_stack_depth: Option<usize>,
+) -> Result<TuringMachine, Error> {
+    let contents = fs::read_to_string(path)?;
+    TuringMachine::new_with_limits(parse_program(&contents)?, max_steps, max_stack_depth)
+}
+
+/// Runs the static non-halting analysis against `path` and prints a warning
+/// to stderr if it finds a proof, without affecting the compile itself.
+fn warn_if_non_halting(path: &PathBuf) -> Result<(), Error> {
+    let contents = fs::read_to_string(path)?;
+    let program = parse_program(&contents)?.validate_into(&ValidationOptions::default())?;
+    if let Termination::ProvenNonHalting { witness } = analyze_termination(&program) {
+        eprintln!("warning: this machine can never halt ({})", witness);
+    }
+    Ok(())
 }
 
 fn tm_to_file(tm: &TuringMachine, path: &PathBuf) -> Result<(), Error> {
@@ -48,19 +175,182 @@ fn run(opt: Opt) -> Result<(), Error> {
         Opt::Execute {
             input_file,
             tape_input,
+            max_steps,
+            max_stack_depth,
         } => {
-            let tm = tm_from_file(&input_file)?;
-            tm.run(tape_input)?;
+            let tm = tm_from_file_with_limits(&input_file, max_steps, max_stack_depth)?;
+            // Mirrors `Opt::Test`'s convention of a nonzero exit on failure.
+            if tm.run(&tape_input)? != Outcome::Accepted {
+                process::exit(1);
+            }
             Ok(())
         }
         Opt::Compile {
             input_file,
             output_file,
+            check_termination,
         } => {
+            if check_termination {
+                warn_if_non_halting(&input_file)?;
+            }
             let tm = tm_from_file(&input_file)?;
             tm_to_file(&tm, &output_file)
         }
+        Opt::CompileNative {
+            input_file,
+            output_file,
+            check_termination,
+        } => {
+            if check_termination {
+                warn_if_non_halting(&input_file)?;
+            }
+            let contents = fs::read_to_string(&input_file)?;
+            let source = compile_native(parse_program(&contents)?)?;
+            fs::write(&output_file, source)?;
+            Ok(())
+        }
+        Opt::ExecRocketlang {
+            input_file,
+            tape_input,
+        } => {
+            let src = fs::read_to_string(&input_file)?;
+            tm::execute_rocketlang(&src, tape_input.as_bytes(), &mut io::stdout())?;
+            Ok(())
+        }
+        Opt::CompileBytecode {
+            input_file,
+            output_file,
+            check_termination,
+        } => {
+            if check_termination {
+                warn_if_non_halting(&input_file)?;
+            }
+            let contents = fs::read_to_string(&input_file)?;
+            let bytecode = compile_bytecode(parse_program(&contents)?)?;
+            fs::write(&output_file, bytecode.to_string())?;
+            Ok(())
+        }
+        Opt::ExecBytecode {
+            input_file,
+            tape_input,
+        } => {
+            let src = match input_file {
+                Some(path) => fs::read_to_string(&path)?,
+                None => {
+                    let mut src = String::new();
+                    io::stdin().read_to_string(&mut src)?;
+                    src
+                }
+            };
+            execute_bytecode(&src, tape_input.as_bytes(), &mut io::stdout())?;
+            Ok(())
+        }
+        Opt::Debug {
+            input_file,
+            tape_input,
+            max_steps,
+            max_stack_depth,
+        } => {
+            let tm = tm_from_file_with_limits(&input_file, max_steps, max_stack_depth)?;
+            debug(&tm, &tape_input)
+        }
+        Opt::Test { manifest } => {
+            let manifest_path = if manifest.is_dir() {
+                manifest.join("manifest.json")
+            } else {
+                manifest
+            };
+            if !test_runner::run_tests(&manifest_path)? {
+                process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Drives an interactive, one-instruction-at-a-time debugging session over
+/// the compiled stack machine for `tm`, printing machine state after every
+/// step and accepting `continue`/`step N`/`break` commands from stdin.
+fn debug(tm: &TuringMachine, tape_input: &str) -> Result<(), Error> {
+    let instructions = tm.instructions();
+    let mut machine = tm.new_stack_machine();
+    let mut stepper = Stepper::new(instructions);
+    let mut reader = io::Cursor::new(tape_input.as_bytes().to_vec()).bytes();
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    let mut breakpoints: Vec<Breakpoint> = Vec::new();
+
+    let print_state = |machine: &StackMachine| {
+        println!(
+            "step {}: active={} inactive={} stack={:?}",
+            machine.steps_executed(),
+            machine.active_var(),
+            machine.inactive_var(),
+            machine.stack()
+        );
+    };
+
+    // Does a single step, printing the resulting state. Returns false once
+    // the program has finished.
+    let mut do_step = |machine: &mut StackMachine,
+                       stepper: &mut Stepper,
+                       reader: &mut io::Bytes<io::Cursor<Vec<u8>>>,
+                       out: &mut dyn Write|
+     -> Result<bool, Error> {
+        let outcome = stepper.step(machine, reader, out)?;
+        print_state(machine);
+        Ok(outcome == StepOutcome::Ran)
+    };
+
+    loop {
+        print!("(tmdb) > ");
+        stdout.lock().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let mut out = stdout.lock();
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            None => continue,
+            Some("quit") | Some("q") => break,
+            Some("step") | Some("s") => {
+                let count: u64 = words.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+                for _ in 0..count {
+                    if !do_step(&mut machine, &mut stepper, &mut reader, &mut out)? {
+                        break;
+                    }
+                }
+            }
+            Some("break") | Some("b") => match words.next() {
+                Some("active") => breakpoints.push(Breakpoint::ActiveEqInactive),
+                Some(step_str) => match step_str.parse() {
+                    Ok(step) => breakpoints.push(Breakpoint::AtStep(step)),
+                    Err(_) => println!("usage: break <step> | break active"),
+                },
+                None => println!("usage: break <step> | break active"),
+            },
+            Some("continue") | Some("c") => {
+                let run_until =
+                    stepper.continue_until(&mut machine, &mut reader, &mut out, &breakpoints)?;
+                print_state(&machine);
+                if run_until == RunUntil::BreakpointHit {
+                    println!("breakpoint hit");
+                }
+            }
+            Some(other) => {
+                println!("unknown command: {}", other);
+            }
+        }
+
+        if stepper.is_halted() {
+            println!("machine halted");
+        }
     }
+    Ok(())
 }
 
 fn main() {
@@ -0,0 +1,125 @@
+use failure::Error;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tm::{parse_program, TuringMachine};
+
+/// A manifest describing a suite of regression cases for one or more
+/// compiled Turing machines, loaded via `tmcli test <dir>`.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    cases: Vec<TestCase>,
+}
+
+/// A single case: run `machine` on `tape` and check the result.
+#[derive(Debug, Deserialize)]
+struct TestCase {
+    /// Path to the machine's `.tm` definition, relative to the manifest.
+    machine: PathBuf,
+    /// The tape input to feed the machine.
+    tape: String,
+    /// The output the machine is expected to produce, if it's expected to
+    /// run successfully.
+    expect_output: Option<String>,
+    /// If true, this case is expected to fail (e.g. a malformed program
+    /// hitting a runtime fault), rather than produce `expect_output`.
+    #[serde(default)]
+    expect_fault: bool,
+}
+
+/// The result of running one `TestCase`.
+struct CaseResult {
+    name: String,
+    passed: bool,
+    detail: Option<String>,
+}
+
+/// Loads the manifest at `manifest_path`, runs every case against the
+/// `StackMachine`-backed `TuringMachine`, and prints a pass/fail summary.
+/// Returns `true` iff every case passed.
+pub fn run_tests(manifest_path: &Path) -> Result<bool, Error> {
+    let manifest_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let manifest: Manifest =
+        serde_json::from_str(&fs::read_to_string(manifest_path)?)?;
+
+    let results: Vec<CaseResult> = manifest
+        .cases
+        .iter()
+        .map(|case| run_case(manifest_dir, case))
+        .collect();
+
+    let passed_count = results.iter().filter(|r| r.passed).count();
+    for result in &results {
+        match &result.detail {
+            Some(detail) if !result.passed => {
+                println!(
+                    "{} {}: {}",
+                    if result.passed { "PASS" } else { "FAIL" },
+                    result.name,
+                    detail
+                );
+            }
+            _ => {
+                println!(
+                    "{} {}",
+                    if result.passed { "PASS" } else { "FAIL" },
+                    result.name
+                );
+            }
+        }
+    }
+    println!("{}/{} cases passed", passed_count, results.len());
+
+    Ok(passed_count == results.len())
+}
+
+/// Runs a single case, comparing the actual outcome (success + output, or
+/// failure) against what the case expects.
+fn run_case(manifest_dir: &Path, case: &TestCase) -> CaseResult {
+    let name = case.machine.display().to_string();
+    let actual = execute(manifest_dir, case);
+
+    let (passed, detail) = match (&actual, case.expect_fault) {
+        (Err(_), true) => (true, None),
+        (Ok(output), false) => {
+            match &case.expect_output {
+                Some(expected) if expected == output => (true, None),
+                Some(expected) => (
+                    false,
+                    Some(format!(
+                        "expected output {:?}, got {:?}",
+                        expected, output
+                    )),
+                ),
+                // No expectation given; merely running without faulting
+                // counts as a pass.
+                None => (true, None),
+            }
+        }
+        (Err(error), false) => {
+            (false, Some(format!("unexpected fault: {}", error)))
+        }
+        (Ok(output), true) => (
+            false,
+            Some(format!(
+                "expected a fault, but machine ran and produced {:?}",
+                output
+            )),
+        ),
+    };
+
+    CaseResult {
+        name,
+        passed,
+        detail,
+    }
+}
+
+/// Loads and runs the machine for one case, returning its output.
+fn execute(manifest_dir: &Path, case: &TestCase) -> Result<String, Error> {
+    let machine_path = manifest_dir.join(&case.machine);
+    let contents = fs::read_to_string(&machine_path)?;
+    let tm = TuringMachine::new(parse_program(&contents)?)?;
+    let output = tm.run_with_output(&case.tape)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
@@ -1,14 +1,87 @@
 use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
 
-/// Number of bits used to represent one character in our alphabet.
-/// Restricted to ASCII to maximize stack length when it gets encoded to a
-/// single int.
-pub const CHAR_SIZE_BITS: usize = 7;
+pub type StateId = usize;
+
+/// Configuration for a machine's alphabet, i.e. how many distinct characters
+/// it can recognize. Expressed as a bit width rather than a raw count so
+/// `ALPHABET_SIZE` (now a per-program value instead of a hardcoded constant)
+/// is always a power of 2, which the tape-encoding math in `compile.rs`
+/// relies on.
+///
+/// Note that the stack machine's `ReadToActive`/`PrintActive` exchange raw
+/// bytes with the outside world, so `char_bits` above 8 isn't usable yet;
+/// `Program` validation rejects it (see `CompilerErrorKind::AlphabetTooWide`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Alphabet {
+    /// Number of bits used to represent one character. The alphabet then
+    /// contains exactly `2^char_bits` characters; 0 is always reserved as
+    /// the empty/blank char.
+    pub char_bits: u32,
+}
 
-/// The number of characters that our machine can recognize.
-pub const ALPHABET_SIZE: u8 = 1 << CHAR_SIZE_BITS; // 1 << n == 2^n
+impl Alphabet {
+    /// The original hardcoded alphabet: 7-bit, i.e. ASCII.
+    pub const ASCII: Self = Alphabet { char_bits: 7 };
 
-pub type StateId = usize;
+    /// The number of distinct characters this alphabet can represent.
+    pub fn size(&self) -> u32 {
+        1 << self.char_bits
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self::ASCII
+    }
+}
+
+/// One character in a machine's alphabet. This can be constructed from
+/// either a raw numeric value (for small or custom alphabets) or a Unicode
+/// scalar value (for alphabets wide enough to represent real text), and
+/// converts back to a `u32` for encoding onto the tape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Char {
+    /// A raw numeric code point in the alphabet.
+    Num(u32),
+    /// A Unicode scalar value.
+    Codepoint(char),
+}
+
+impl Char {
+    /// The reserved empty/blank character, valid in every alphabet.
+    pub const BLANK: Self = Char::Num(0);
+
+    /// This character's numeric value within the alphabet.
+    pub fn to_u32(&self) -> u32 {
+        match self {
+            Char::Num(n) => *n,
+            Char::Codepoint(c) => *c as u32,
+        }
+    }
+}
+
+impl From<u32> for Char {
+    fn from(n: u32) -> Self {
+        Char::Num(n)
+    }
+}
+
+impl From<char> for Char {
+    fn from(c: char) -> Self {
+        Char::Codepoint(c)
+    }
+}
+
+impl Display for Char {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Char::Codepoint(c) => write!(f, "{:?}", c),
+            Char::Num(n) => write!(f, "{}", n),
+        }
+    }
+}
 
 /// The different types of instructions that the TM can execute during a
 /// transition.
@@ -16,21 +89,49 @@ pub type StateId = usize;
 /// This is not the most common way of defining a TM (usually you write AND
 /// move in each transition), but this is how KG taught us, and who am I to
 /// question him.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum TapeInstruction {
     Left,
     Right,
-    Write(char),
+    Write(Char),
+}
+
+/// Which tape char(s) a transition applies to. Lets one transition cover a
+/// whole class of symbols instead of requiring one rule per concrete char.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum MatchPattern {
+    /// Matches exactly one character.
+    Exact(Char),
+    /// Matches any of the given characters (`0 | 1`-style alternation).
+    AnyOf(Vec<Char>),
+    /// Matches any character in the alphabet. Typically used as a
+    /// lowest-priority fallback alongside other transitions in the same
+    /// state: codegen gives explicit entries priority and only falls back
+    /// to the wildcard for everything else (see `Compile for [Transition]`).
+    /// A state may only have one (see `Validate for State`).
+    Wildcard,
+}
+
+impl MatchPattern {
+    /// The chars this pattern matches on its own, i.e. everything except
+    /// `Wildcard`, whose coverage depends on the containing `Alphabet`.
+    pub fn exact_chars(&self) -> &[Char] {
+        match self {
+            MatchPattern::Exact(c) => std::slice::from_ref(c),
+            MatchPattern::AnyOf(chars) => chars,
+            MatchPattern::Wildcard => &[],
+        }
+    }
 }
 
-/// One transition, defined by a (state, char) pair. This consists of a tape
-/// instruction, and a destination state.
+/// One transition, defined by a (state, char) pair. This consists of a
+/// sequence of tape instructions, and a destination state.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Transition {
-    /// The character on the tape that triggers this transition
-    pub match_char: char,
-    /// The instruction to execute on the tape (L/R/W)
-    pub tape_instruction: TapeInstruction,
+    /// The tape char(s) that trigger this transition
+    pub match_pattern: MatchPattern,
+    /// The instructions to execute on the tape (L/R/W), in order
+    pub tape_instructions: Vec<TapeInstruction>,
     /// The state to transition to next
     pub next_state: StateId,
 }
@@ -53,4 +154,9 @@ pub struct State {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Program {
     pub states: Vec<State>,
+    /// The alphabet this program's characters are drawn from. Defaults to
+    /// ASCII for programs (e.g. older machine definitions) that don't
+    /// specify one.
+    #[serde(default)]
+    pub alphabet: Alphabet,
 }
@@ -0,0 +1,704 @@
+use crate::{ast::Program, stack::SmInstruction, turing::TuringMachine};
+use failure::{Error, Fail};
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, Read, Write},
+};
+
+/// One instruction in the flat bytecode target. This is a simple
+/// stack-based VM: a small fixed "data area" plays the same role as
+/// `SmInstruction`'s active/inactive registers, and the operand stack plays
+/// the same role as `StackMachine`'s stack (so the tape encoding compiled by
+/// `Compile` carries over unchanged). Unlike `SmInstruction`, there's no
+/// `If`/`While` nesting - control flow is `jz`/`jmp` to absolute offsets,
+/// resolved once by `lower`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BcOp {
+    /// Pushes a literal constant onto the stack.
+    Push(i64),
+    /// Reads one byte from input and pushes it, or 0 if input is exhausted.
+    /// The bytecode equivalent of `SmInstruction::ReadToActive`.
+    Read,
+    /// Pops the stack and stores the value into the given data slot.
+    Store(usize),
+    /// Pushes the value of the given data slot.
+    Fetch(usize),
+    /// Pops two values (b then a) and pushes `a + b`.
+    Add,
+    /// Pops two values (b then a) and pushes `1` if `a < b`, else `0`.
+    Lt,
+    /// Pops the stack; jumps to the given offset if the value is `0`.
+    Jz(usize),
+    /// Jumps unconditionally to the given offset.
+    Jmp(usize),
+    /// Pops the stack and prints its low byte as a single char.
+    Prti,
+    /// Prints the interned string constant at the given index verbatim.
+    Prts(usize),
+    /// Stops execution.
+    Halt,
+}
+
+impl Display for BcOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BcOp::Push(n) => write!(f, "push {}", n),
+            BcOp::Read => write!(f, "read"),
+            BcOp::Store(slot) => write!(f, "store {}", slot),
+            BcOp::Fetch(slot) => write!(f, "fetch {}", slot),
+            BcOp::Add => write!(f, "add"),
+            BcOp::Lt => write!(f, "lt"),
+            BcOp::Jz(addr) => write!(f, "jz {}", addr),
+            BcOp::Jmp(addr) => write!(f, "jmp {}", addr),
+            BcOp::Prti => write!(f, "prti"),
+            BcOp::Prts(idx) => write!(f, "prts {}", idx),
+            BcOp::Halt => write!(f, "halt"),
+        }
+    }
+}
+
+/// A complete lowered program: how many data slots it needs, the string
+/// constants it prints (`Prts`' operand indexes into this), and the flat,
+/// jump-resolved instruction stream itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bytecode {
+    pub data_size: usize,
+    pub constants: Vec<String>,
+    pub ops: Vec<BcOp>,
+}
+
+impl Display for Bytecode {
+    /// The textual assembly format: a header giving the data size and
+    /// string-constant count, the constants themselves (one per line,
+    /// quoted), then one `offset mnemonic [operand]` line per instruction.
+    /// This is the inverse of `parse_bytecode`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "data {}", self.data_size)?;
+        writeln!(f, "strings {}", self.constants.len())?;
+        for constant in &self.constants {
+            writeln!(f, "{}", escape_string(constant))?;
+        }
+        for (offset, op) in self.ops.iter().enumerate() {
+            writeln!(f, "{} {}", offset, op)?;
+        }
+        Ok(())
+    }
+}
+
+/// Data slots used by lowered code: the active and inactive registers carry
+/// straight over from `SmInstruction`, plus one scratch slot `Swap` needs
+/// since the bytecode VM has no direct "exchange two registers" primitive.
+const SLOT_ACTIVE: usize = 0;
+const SLOT_INACTIVE: usize = 1;
+const SLOT_SCRATCH: usize = 2;
+const DATA_SIZE: usize = 3;
+
+/// Shifts every jump target in `ops` by `delta`. `ops` is assumed to already
+/// be fully flat (no nested `If`/`While`), with jump targets relative to
+/// `ops[0]`; this re-bases them once `ops` is spliced into a larger,
+/// already-flat sequence starting `delta` instructions later.
+fn relocate(ops: &mut [BcOp], delta: usize) {
+    for op in ops {
+        match op {
+            BcOp::Jz(addr) | BcOp::Jmp(addr) => *addr += delta,
+            _ => {}
+        }
+    }
+}
+
+/// Recognizes the `Comment` that `compile.rs`'s `print_string!` macro emits
+/// just before the primitive instructions that print a literal string one
+/// char at a time, e.g. `Comment("Print 'ACCEPT'")`.
+fn print_marker(comment: &str) -> Option<&str> {
+    comment.strip_prefix("Print '")?.strip_suffix('\'')
+}
+
+/// Recognizes `print_string!`'s matching closing marker,
+/// `Comment("End print 'ACCEPT'")`.
+fn is_end_print_marker(comment: &str) -> bool {
+    comment.starts_with("End print '")
+}
+
+/// Interns `s`, returning its index in `constants` (reusing an existing
+/// entry if this string was already interned).
+fn intern(constants: &mut Vec<String>, s: &str) -> usize {
+    match constants.iter().position(|existing| existing == s) {
+        Some(idx) => idx,
+        None => {
+            constants.push(s.to_owned());
+            constants.len() - 1
+        }
+    }
+}
+
+/// Lowers one primitive (non-`If`/`While`) `SmInstruction` to bytecode.
+fn lower_primitive(instruction: &SmInstruction) -> Vec<BcOp> {
+    match instruction {
+        SmInstruction::ReadToActive => vec![BcOp::Read, BcOp::Store(SLOT_ACTIVE)],
+        SmInstruction::PrintActive => vec![BcOp::Fetch(SLOT_ACTIVE), BcOp::Prti],
+        SmInstruction::IncrActive => vec![
+            BcOp::Fetch(SLOT_ACTIVE),
+            BcOp::Push(1),
+            BcOp::Add,
+            BcOp::Store(SLOT_ACTIVE),
+        ],
+        SmInstruction::DecrActive => vec![
+            BcOp::Fetch(SLOT_ACTIVE),
+            BcOp::Push(-1),
+            BcOp::Add,
+            BcOp::Store(SLOT_ACTIVE),
+        ],
+        SmInstruction::SaveActive => {
+            vec![BcOp::Fetch(SLOT_ACTIVE), BcOp::Store(SLOT_INACTIVE)]
+        }
+        SmInstruction::Swap => vec![
+            BcOp::Fetch(SLOT_ACTIVE),
+            BcOp::Store(SLOT_SCRATCH),
+            BcOp::Fetch(SLOT_INACTIVE),
+            BcOp::Store(SLOT_ACTIVE),
+            BcOp::Fetch(SLOT_SCRATCH),
+            BcOp::Store(SLOT_INACTIVE),
+        ],
+        SmInstruction::PushZero => vec![BcOp::Push(0)],
+        SmInstruction::PushActive => vec![BcOp::Fetch(SLOT_ACTIVE)],
+        SmInstruction::PopToActive => vec![BcOp::Store(SLOT_ACTIVE)],
+        // Compiled programs always run with errors disabled (see the single
+        // `ToggleErrors` at the very start of `Compile for Valid<Program>`),
+        // and the bytecode VM never surfaces a pop-on-empty-stack fault
+        // (see `BytecodeMachine::pop`), so there's nothing to toggle.
+        SmInstruction::ToggleErrors => vec![],
+        // Debug-only instrumentation, not part of the machine's logical
+        // output; dropped, same as `ToRocketlang` drops `Comment`.
+        SmInstruction::PrintState | SmInstruction::DebugPrint(..) => vec![],
+        SmInstruction::Comment(_) => vec![],
+        SmInstruction::InlineComment(inner, _) => lower_primitive(inner),
+        SmInstruction::If(_) | SmInstruction::While(_) => {
+            unreachable!("If/While are lowered by lower_block, not lower_primitive")
+        }
+    }
+}
+
+/// Lowers `If(body)`, i.e. "run `body` iff active == inactive", to
+/// `active == inactive  <=>  !(active < inactive) && !(inactive < active)`.
+fn lower_if(body_ops: &[BcOp]) -> Vec<BcOp> {
+    // Offsets below are relative to this returned Vec's own start (0); the
+    // caller relocates them once this block is spliced into a larger one.
+    const CHECK_B: usize = 5;
+    let body_start = 10;
+    let end = body_start + body_ops.len();
+
+    let mut out = vec![
+        BcOp::Fetch(SLOT_ACTIVE),
+        BcOp::Fetch(SLOT_INACTIVE),
+        BcOp::Lt,
+        BcOp::Jz(CHECK_B),
+        BcOp::Jmp(end),
+        BcOp::Fetch(SLOT_INACTIVE),
+        BcOp::Fetch(SLOT_ACTIVE),
+        BcOp::Lt,
+        BcOp::Jz(body_start),
+        BcOp::Jmp(end),
+    ];
+    let mut body_ops = body_ops.to_vec();
+    relocate(&mut body_ops, body_start);
+    out.extend(body_ops);
+    out
+}
+
+/// Lowers `While(body)`, i.e. "loop `body` while active > 0", to a
+/// `0 < active` check before each iteration.
+fn lower_while(body_ops: &[BcOp]) -> Vec<BcOp> {
+    let body_start = 4;
+    let end = body_start + body_ops.len() + 1; // +1 for the trailing Jmp
+
+    let mut out = vec![
+        BcOp::Push(0),
+        BcOp::Fetch(SLOT_ACTIVE),
+        BcOp::Lt,
+        BcOp::Jz(end),
+    ];
+    let mut body_ops = body_ops.to_vec();
+    relocate(&mut body_ops, body_start);
+    out.extend(body_ops);
+    out.push(BcOp::Jmp(0));
+    out
+}
+
+/// Lowers a full instruction block (resolving any nested `If`/`While` into
+/// flat, locally-relative jumps), detecting and interning
+/// `print_string!`-shaped literal prints along the way.
+fn lower_block(instructions: &[SmInstruction], constants: &mut Vec<String>) -> Vec<BcOp> {
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < instructions.len() {
+        if let SmInstruction::Comment(comment) = &instructions[i] {
+            if let Some(s) = print_marker(comment) {
+                let relative_end = instructions[i + 1..]
+                    .iter()
+                    .position(|instr| {
+                        matches!(instr, SmInstruction::Comment(c) if is_end_print_marker(c))
+                    })
+                    .expect(
+                        "print_string! marker Comment without a matching \
+                         'End print' marker",
+                    );
+                let idx = intern(constants, s);
+                out.push(BcOp::Prts(idx));
+                i += relative_end + 2; // skip the body and the end marker
+                continue;
+            }
+        }
+
+        match &instructions[i] {
+            SmInstruction::If(body) => {
+                let mut block = lower_if(&lower_block(body, constants));
+                relocate(&mut block, out.len());
+                out.extend(block);
+            }
+            SmInstruction::While(body) => {
+                let mut block = lower_while(&lower_block(body, constants));
+                relocate(&mut block, out.len());
+                out.extend(block);
+            }
+            other => out.extend(lower_primitive(other)),
+        }
+        i += 1;
+    }
+
+    out
+}
+
+/// Lowers a compiled `SmInstruction` stream (e.g. from
+/// `TuringMachine::instructions`) into flat bytecode, resolving structured
+/// `If`/`While` blocks into absolute jump offsets and interning any
+/// `print_string!`-shaped literal prints (ACCEPT/REJECT) into the constant
+/// table instead of replaying their per-character primitives.
+pub fn lower(instructions: &[SmInstruction]) -> Bytecode {
+    let mut constants = Vec::new();
+    let mut ops = lower_block(instructions, &mut constants);
+    ops.push(BcOp::Halt);
+    Bytecode {
+        data_size: DATA_SIZE,
+        constants,
+        ops,
+    }
+}
+
+/// Compiles `program` to its optimized stack-machine instructions (same as
+/// `TuringMachine::new`) and lowers those to flat bytecode, for producing a
+/// bytecode target from source the same way `compile_native` does for C.
+pub fn compile_bytecode(program: Program) -> Result<Bytecode, Error> {
+    let tm = TuringMachine::new(program)?;
+    Ok(lower(tm.instructions()))
+}
+
+fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn unescape_string(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '"' => out.push('"'),
+            '\\' => out.push('\\'),
+            'n' => out.push('\n'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Errors that can occur while parsing the textual bytecode assembly format
+/// back into a `Bytecode`.
+#[derive(Debug, Fail, PartialEq)]
+pub enum BytecodeParseError {
+    #[fail(display = "Missing or malformed header (expected \"data N\")")]
+    MalformedHeader,
+    #[fail(
+        display = "Missing or malformed string constant on line {}",
+        0
+    )]
+    MalformedStringConstant(usize),
+    #[fail(display = "Line {}: malformed instruction {:?}", 0, 1)]
+    MalformedLine(usize, String),
+    #[fail(display = "Line {}: unknown opcode {:?}", 0, 1)]
+    UnknownOpcode(usize, String),
+    #[fail(
+        display = "Line {}: offset {} doesn't match the expected {}",
+        0, 1, 2
+    )]
+    OffsetMismatch(usize, usize, usize),
+}
+
+/// Parses the textual bytecode assembly format produced by `Bytecode`'s
+/// `Display` impl back into a `Bytecode`. The inverse of that impl.
+pub fn parse_bytecode(text: &str) -> Result<Bytecode, BytecodeParseError> {
+    let mut lines = text.lines().enumerate();
+
+    let data_size = lines
+        .next()
+        .and_then(|(_, line)| line.trim().strip_prefix("data "))
+        .and_then(|n| n.trim().parse().ok())
+        .ok_or(BytecodeParseError::MalformedHeader)?;
+
+    let string_count: usize = lines
+        .next()
+        .and_then(|(_, line)| line.trim().strip_prefix("strings "))
+        .and_then(|n| n.trim().parse().ok())
+        .ok_or(BytecodeParseError::MalformedHeader)?;
+
+    let mut constants = Vec::with_capacity(string_count);
+    for _ in 0..string_count {
+        let (line_num, line) = lines
+            .next()
+            .ok_or(BytecodeParseError::MalformedHeader)?;
+        let constant = unescape_string(line.trim())
+            .ok_or(BytecodeParseError::MalformedStringConstant(line_num + 1))?;
+        constants.push(constant);
+    }
+
+    let mut ops = Vec::new();
+    for (line_num, line) in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let malformed = || BytecodeParseError::MalformedLine(line_num + 1, line.to_owned());
+
+        let offset: usize = parts.next().and_then(|s| s.parse().ok()).ok_or_else(malformed)?;
+        if offset != ops.len() {
+            return Err(BytecodeParseError::OffsetMismatch(
+                line_num + 1,
+                offset,
+                ops.len(),
+            ));
+        }
+        let mnemonic = parts.next().ok_or_else(malformed)?;
+        let rest = parts.next();
+        let operand = || -> Result<usize, BytecodeParseError> {
+            rest.and_then(|s| s.parse().ok()).ok_or_else(malformed)
+        };
+        let signed_operand = || -> Result<i64, BytecodeParseError> {
+            rest.and_then(|s| s.parse().ok()).ok_or_else(malformed)
+        };
+
+        ops.push(match mnemonic {
+            "push" => BcOp::Push(signed_operand()?),
+            "read" => BcOp::Read,
+            "store" => BcOp::Store(operand()?),
+            "fetch" => BcOp::Fetch(operand()?),
+            "add" => BcOp::Add,
+            "lt" => BcOp::Lt,
+            "jz" => BcOp::Jz(operand()?),
+            "jmp" => BcOp::Jmp(operand()?),
+            "prti" => BcOp::Prti,
+            "prts" => BcOp::Prts(operand()?),
+            "halt" => BcOp::Halt,
+            _ => return Err(BytecodeParseError::UnknownOpcode(line_num + 1, mnemonic.to_owned())),
+        });
+    }
+
+    Ok(Bytecode {
+        data_size,
+        constants,
+        ops,
+    })
+}
+
+/// Faults raised while executing a `Bytecode` program on a
+/// `BytecodeMachine`.
+#[derive(Debug, Fail)]
+pub enum BcFault {
+    #[fail(display = "Input exhausted")]
+    InputExhausted,
+    #[fail(display = "Output error: {}", 0)]
+    OutputError(io::Error),
+    #[fail(display = "Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[fail(display = "Jump target {} is out of bounds", 0)]
+    InvalidJumpTarget(usize),
+    #[fail(display = "Invalid data slot {}", 0)]
+    InvalidDataSlot(usize),
+    #[fail(display = "Invalid string constant index {}", 0)]
+    InvalidStringConstant(usize),
+}
+
+/// Interprets `Bytecode` programs: a data-slot register file plus an
+/// operand stack, the same two pieces of state `StackMachine` uses for the
+/// `SmInstruction` target, just addressed through `Store`/`Fetch` instead
+/// of dedicated active/inactive registers.
+pub struct BytecodeMachine {
+    data: Vec<i64>,
+    stack: Vec<i64>,
+    pc: usize,
+}
+
+impl BytecodeMachine {
+    pub fn new(data_size: usize) -> Self {
+        BytecodeMachine {
+            data: vec![0; data_size],
+            stack: Vec::new(),
+            pc: 0,
+        }
+    }
+
+    /// Pops the stack, or yields `0` if it's empty - mirroring
+    /// `StackMachine::pop`'s errors-disabled behavior, the only mode
+    /// `compile_bytecode`'d programs run in (see `Compile for
+    /// Valid<Program>`'s leading `ToggleErrors`, which `lower` drops
+    /// entirely rather than threading the toggle through).
+    fn pop(&mut self) -> i64 {
+        self.stack.pop().unwrap_or(0)
+    }
+
+    /// Runs `bytecode` to completion (i.e. until `Halt`) against the given
+    /// input/output.
+    pub fn run<R: Read, W: Write>(
+        &mut self,
+        bytecode: &Bytecode,
+        reader: R,
+        writer: &mut W,
+    ) -> Result<(), BcFault> {
+        let mut bytes = reader.bytes();
+        loop {
+            let op = bytecode
+                .ops
+                .get(self.pc)
+                .ok_or(BcFault::InvalidJumpTarget(self.pc))?;
+            self.pc += 1;
+
+            match op {
+                BcOp::Push(n) => self.stack.push(*n),
+                BcOp::Read => {
+                    let value = match bytes.next() {
+                        Some(byte) => i64::from(byte.map_err(|_| BcFault::InputExhausted)?),
+                        None => 0,
+                    };
+                    self.stack.push(value);
+                }
+                BcOp::Store(slot) => {
+                    let value = self.pop();
+                    *self
+                        .data
+                        .get_mut(*slot)
+                        .ok_or(BcFault::InvalidDataSlot(*slot))? = value;
+                }
+                BcOp::Fetch(slot) => {
+                    let value = *self.data.get(*slot).ok_or(BcFault::InvalidDataSlot(*slot))?;
+                    self.stack.push(value);
+                }
+                BcOp::Add => {
+                    let b = self.pop();
+                    let a = self.pop();
+                    self.stack
+                        .push(a.checked_add(b).ok_or(BcFault::ArithmeticOverflow)?);
+                }
+                BcOp::Lt => {
+                    let b = self.pop();
+                    let a = self.pop();
+                    self.stack.push(if a < b { 1 } else { 0 });
+                }
+                BcOp::Jz(addr) => {
+                    if self.pop() == 0 {
+                        self.pc = *addr;
+                    }
+                }
+                BcOp::Jmp(addr) => self.pc = *addr,
+                BcOp::Prti => {
+                    let value = self.pop();
+                    writer
+                        .write_all(&[(value & 0xFF) as u8])
+                        .map_err(BcFault::OutputError)?;
+                }
+                BcOp::Prts(idx) => {
+                    let s = bytecode
+                        .constants
+                        .get(*idx)
+                        .ok_or(BcFault::InvalidStringConstant(*idx))?;
+                    writer.write_all(s.as_bytes()).map_err(BcFault::OutputError)?;
+                }
+                BcOp::Halt => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Either stage of `execute` (parsing or running) can fail.
+#[derive(Debug, Fail)]
+pub enum BytecodeExecError {
+    #[fail(display = "{}", 0)]
+    Parse(#[cause] BytecodeParseError),
+    #[fail(display = "{}", 0)]
+    Fault(#[cause] BcFault),
+}
+
+impl From<BytecodeParseError> for BytecodeExecError {
+    fn from(error: BytecodeParseError) -> Self {
+        BytecodeExecError::Parse(error)
+    }
+}
+
+impl From<BcFault> for BytecodeExecError {
+    fn from(fault: BcFault) -> Self {
+        BytecodeExecError::Fault(fault)
+    }
+}
+
+/// Parses and runs bytecode assembly source against the given input/output,
+/// for running a compiled machine without a Rocketlang runtime.
+pub fn execute<R: Read, W: Write>(
+    src: &str,
+    reader: R,
+    writer: &mut W,
+) -> Result<(), BytecodeExecError> {
+    let bytecode = parse_bytecode(src)?;
+    let mut machine = BytecodeMachine::new(bytecode.data_size);
+    machine.run(&bytecode, reader, writer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_bytecode(bytecode: &Bytecode, input: &[u8]) -> Vec<u8> {
+        let mut machine = BytecodeMachine::new(bytecode.data_size);
+        let mut output = Vec::new();
+        machine.run(bytecode, input, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_lower_flat_instructions() {
+        let bytecode = lower(&[SmInstruction::IncrActive, SmInstruction::DecrActive]);
+        assert_eq!(
+            bytecode.ops,
+            vec![
+                BcOp::Fetch(SLOT_ACTIVE),
+                BcOp::Push(1),
+                BcOp::Add,
+                BcOp::Store(SLOT_ACTIVE),
+                BcOp::Fetch(SLOT_ACTIVE),
+                BcOp::Push(-1),
+                BcOp::Add,
+                BcOp::Store(SLOT_ACTIVE),
+                BcOp::Halt,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip_through_text_format() {
+        let bytecode = lower(&[
+            SmInstruction::IncrActive,
+            SmInstruction::If(vec![SmInstruction::PushZero]),
+            SmInstruction::While(vec![SmInstruction::DecrActive]),
+        ]);
+        let text = bytecode.to_string();
+        assert_eq!(parse_bytecode(&text).unwrap(), bytecode);
+    }
+
+    #[test]
+    fn test_if_runs_body_only_when_equal() {
+        // Both registers start at 0, so the If should fire immediately.
+        let bytecode = lower(&[SmInstruction::If(vec![SmInstruction::IncrActive])]);
+        let mut machine = BytecodeMachine::new(bytecode.data_size);
+        machine.run(&bytecode, &b""[..], &mut Vec::new()).unwrap();
+        assert_eq!(machine.data[SLOT_ACTIVE], 1);
+    }
+
+    #[test]
+    fn test_if_skips_body_when_not_equal() {
+        let bytecode = lower(&[
+            SmInstruction::IncrActive, // active = 1, inactive = 0: not equal
+            SmInstruction::If(vec![SmInstruction::IncrActive, SmInstruction::IncrActive]),
+        ]);
+        let mut machine = BytecodeMachine::new(bytecode.data_size);
+        machine.run(&bytecode, &b""[..], &mut Vec::new()).unwrap();
+        assert_eq!(machine.data[SLOT_ACTIVE], 1);
+    }
+
+    #[test]
+    fn test_while_loops_until_zero() {
+        let bytecode = lower(&[
+            SmInstruction::IncrActive,
+            SmInstruction::IncrActive,
+            SmInstruction::IncrActive,
+            SmInstruction::While(vec![SmInstruction::DecrActive]),
+        ]);
+        let mut machine = BytecodeMachine::new(bytecode.data_size);
+        machine.run(&bytecode, &b""[..], &mut Vec::new()).unwrap();
+        assert_eq!(machine.data[SLOT_ACTIVE], 0);
+    }
+
+    #[test]
+    fn test_interns_print_string_marker() {
+        let instructions = vec![
+            SmInstruction::Comment("Print 'OK'".to_owned()),
+            SmInstruction::IncrActive,
+            SmInstruction::IncrActive,
+            SmInstruction::PrintActive,
+            SmInstruction::Comment("End print 'OK'".to_owned()),
+        ];
+        let bytecode = lower(&instructions);
+        assert_eq!(bytecode.constants, vec!["OK".to_owned()]);
+        assert_eq!(bytecode.ops, vec![BcOp::Prts(0), BcOp::Halt]);
+    }
+
+    #[test]
+    fn test_stack_survives_arithmetic_scratch_work() {
+        // PushZero followed by Swap (computation scratch work) shouldn't
+        // disturb a value already sitting on the stack underneath it.
+        let bytecode = lower(&[
+            SmInstruction::IncrActive,
+            SmInstruction::PushActive,
+            SmInstruction::PushZero,
+            SmInstruction::Swap,
+        ]);
+        let output = run_bytecode(&bytecode, b"");
+        assert!(output.is_empty());
+
+        let mut machine = BytecodeMachine::new(bytecode.data_size);
+        machine.run(&bytecode, &b""[..], &mut Vec::new()).unwrap();
+        assert_eq!(machine.stack, vec![1, 0]);
+    }
+
+    #[test]
+    fn test_parse_rejects_offset_mismatch() {
+        let text = "data 1\nstrings 0\n0 halt\n2 halt\n";
+        assert_eq!(
+            parse_bytecode(text),
+            Err(BytecodeParseError::OffsetMismatch(4, 2, 1))
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_opcode() {
+        let text = "data 1\nstrings 0\n0 frobnicate\n";
+        assert_eq!(
+            parse_bytecode(text),
+            Err(BytecodeParseError::UnknownOpcode(3, "frobnicate".to_owned()))
+        );
+    }
+}
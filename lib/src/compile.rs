@@ -1,9 +1,8 @@
 use crate::{
-    ast::{Program, State, TapeInstruction, Transition, ALPHABET_SIZE},
+    ast::{Alphabet, MatchPattern, Program, State, TapeInstruction, Transition},
     stack::SmInstruction::{self, *},
     validate::Valid,
 };
-use ascii::AsciiChar;
 use itertools::Itertools;
 use std::{collections::HashMap, iter};
 
@@ -50,33 +49,46 @@ macro_rules! state_comment {
 
 macro_rules! print_string {
     ( $s:expr ) => {
-        iter::once(Comment(format!("Print '{}'", $s))).chain(
-            $s.chars()
-                .chain(iter::once('\n'))
-                .map(|c| {
-                    iter::repeat(IncrActive).take(c as usize).chain(vec![
-                        PrintActive,
-                        PushZero,
-                        PopToActive,
-                    ])
-                })
-                .flatten(),
-        )
+        iter::once(Comment(format!("Print '{}'", $s)))
+            .chain(
+                $s.chars()
+                    .chain(iter::once('\n'))
+                    .map(|c| {
+                        iter::repeat(IncrActive).take(c as usize).chain(vec![
+                            PrintActive,
+                            PushZero,
+                            PopToActive,
+                        ])
+                    })
+                    .flatten(),
+            )
+            // Bookends the marker above so a consumer that wants to treat
+            // this whole expansion as one opaque "print a literal string"
+            // step (e.g. `bytecode::lower`, which interns it instead of
+            // replaying it char-by-char) can find where it ends.
+            .chain(iter::once(Comment(format!("End print '{}'", $s))))
     };
 }
 
-/// Defines compilation steps for a single type.
+/// Defines compilation steps for a single type. Some types need contextual
+/// data to compile correctly, such as the program's alphabet; this trait
+/// defines a type `Context` for that purpose, mirroring `Validate::Context`.
 pub trait Compile {
+    type Context;
+
     /// Generates a sequence of instructions that execute the steps necessary
     /// to process this data type.
-    fn compile(&self) -> Vec<SmInstruction>;
+    fn compile(&self, context: &Self::Context) -> Vec<SmInstruction>;
 }
 
 impl Compile for Valid<Program> {
+    type Context = ();
+
     /// Compiles the given Turing Machine (represented by a series of states)
     /// into a series of stack machine instructions.
-    fn compile(&self) -> Vec<SmInstruction> {
+    fn compile(&self, _context: &Self::Context) -> Vec<SmInstruction> {
         let states = &self.states;
+        let alphabet = &self.alphabet;
         let initial_state = states.iter().find(|state| state.initial).expect(
             "No initial state defined! Something went wrong in validation.",
         );
@@ -134,7 +146,7 @@ impl Compile for Valid<Program> {
                 states
                     .iter()
                     .sorted_by_key(|state| state.id)
-                    .map(State::compile)
+                    .map(|state| state.compile(alphabet))
                     .flatten()
                     // var_a: FREE
                     // var_i: 0
@@ -201,12 +213,23 @@ impl Compile for State {
     /// - Left tape (encoded)
     /// - Head char
     /// - ...Right tape
-    fn compile(&self) -> Vec<SmInstruction> {
+    type Context = Alphabet;
+
+    fn compile(&self, alphabet: &Self::Context) -> Vec<SmInstruction> {
         // The state counter starts at n (desired state #), and counts down to
         // 0. It will hit 0 on the nth state check, and the If condition
         // will match. This means the states have to be sorted by
         // ascending ID, so that State n is the nth block.
 
+        // A Wildcard transition (at most one per state, per `Validate for
+        // State`) isn't part of the sparse sweep below - it has to match
+        // every char the explicit transitions don't, so it's handled here
+        // as the sweep's fallback instead.
+        let wildcard_transition = self
+            .transitions
+            .iter()
+            .find(|transition| transition.match_pattern == MatchPattern::Wildcard);
+
         // Setup logic for switching on the head char
         vec![
             Comment(format!("Check state {}", self.id)),
@@ -227,7 +250,7 @@ impl Compile for State {
                 ]
                 .into_iter()
                 // Generate a big list of Ifs, one for each transition
-                .chain(self.transitions.compile())
+                .chain(self.transitions.compile(alphabet))
                 // Two possible states here. If a transition above executed:
                 // var_a: FREE
                 // var_i: -1
@@ -237,7 +260,7 @@ impl Compile for State {
                 // - ...Right tape
                 //
                 // If no transitions executed (because none of them matched):
-                // var_a: ALPHABET_SIZE
+                // var_a: FREE (the sweep's last counter value)
                 // var_i: Head char
                 // - Left tape (encoded)
                 // - ...Right tape
@@ -263,27 +286,45 @@ impl Compile for State {
                             DecrActive,
                             Swap,        // var_a is free now
                             PopToActive, // Pop LT
-                            Swap,        // var_a = HC, var_i = LT
-                            PushActive,  // Push HC
-                            Swap,        // var_a = LT, var_i = HC
-                            PushActive,  // Push LT
-                            // Reset var_a=0 so we exit the loop, and var_i=0
-                            // because our output contract specifies that.
-                            PushZero,
-                            PopToActive,
-                            SaveActive,
                         ]
                         .into_iter()
-                        // Push the HALT condition
-                        .chain(if self.accepting {
-                            vec![Comment("Push 0 for ACCEPT".into()), PushZero]
-                        } else {
-                            vec![
-                                Comment("Push -1 for REJECT".into()),
-                                DecrActive,
-                                PushActive,
-                                IncrActive,
+                        .chain(match wildcard_transition {
+                            // Restore to the (var_a: FREE, var_i: Head char,
+                            // stack: [Left tape, ...Right tape]) contract
+                            // `Transition::compile` expects, then let it
+                            // take over exactly as if it had matched in the
+                            // sweep above.
+                            Some(transition) => vec![PushActive] // Push LT back
+                                .into_iter()
+                                .chain(transition.compile(alphabet))
+                                .collect(),
+                            // No wildcard: rebuild the full tape and push
+                            // the HALT outcome as the "next state".
+                            None => vec![
+                                Swap,       // var_a = HC, var_i = LT
+                                PushActive, // Push HC
+                                Swap,       // var_a = LT, var_i = HC
+                                PushActive, // Push LT
+                                // Reset var_a=0 so we exit the loop, and
+                                // var_i=0 because our output contract
+                                // specifies that.
+                                PushZero,
+                                PopToActive,
+                                SaveActive,
                             ]
+                            .into_iter()
+                            // Push the HALT condition
+                            .chain(if self.accepting {
+                                vec![Comment("Push 0 for ACCEPT".into()), PushZero]
+                            } else {
+                                vec![
+                                    Comment("Push -1 for REJECT".into()),
+                                    DecrActive,
+                                    PushActive,
+                                    IncrActive,
+                                ]
+                            })
+                            .collect(),
                         })
                         .collect(),
                     ),
@@ -305,6 +346,13 @@ impl Compile for [Transition] {
     /// Compiles the given transitions into a set of If statements with some
     /// logic to count through them and match the correct one.
     ///
+    /// Only covers `Exact`/`AnyOf` transitions; a `Wildcard` transition (at
+    /// most one per state, per `Validate for State`) has to match every char
+    /// the explicit transitions don't, so `Compile for State` handles it
+    /// separately as the fallback once this sweep comes up empty, rather
+    /// than folding it in here and forcing the sweep to walk the full
+    /// alphabet regardless of how sparse the explicit transitions are.
+    ///
     /// ## Input state
     /// var_a: 0
     /// var_i: Head char
@@ -321,64 +369,62 @@ impl Compile for [Transition] {
     /// - ...Right tape
     ///
     /// ### If no transitions executed (because none of them matched)
-    /// var_a: ALPHABET_SIZE
+    /// var_a: FREE (the sweep's last counter value)
     /// var_i: Head char
     /// - Left tape (encoded)
     /// - ...Right tape
-    fn compile(&self) -> Vec<SmInstruction> {
-        // Now we're going to check for a transition on each character. Start at
-        // 0 and count up until we hit the char we're looking for. Note that,
-        // like states, we have to sort the characters so that we can count up
-        // through them. Unlike states though, transition chars aren't
-        // guaranteed to be contiguous so we have to fill the gaps with extra
-        // incrs.
+    type Context = Alphabet;
+
+    fn compile(&self, alphabet: &Self::Context) -> Vec<SmInstruction> {
+        // Now we're going to check for a transition on each character. Start
+        // at 0 and count up until we hit the char we're looking for. Note
+        // that, like states, we have to sort the characters so that we can
+        // count up through them. Unlike states though, transition chars
+        // aren't guaranteed to be contiguous so we have to fill the gaps
+        // with extra incrs.
         // e.g. if we have transitions for c=0 and c=2, we need two incrs
         // between the two Ifs to properly match the second condition.
         //
+        // Crucially, we only walk as far as the largest char actually used:
+        // unlike a full `0..alphabet.size()` sweep, generated code size
+        // tracks the number and spread of chars with transitions, not the
+        // size of the alphabet, which is what makes wide (e.g. full
+        // Unicode codepoint) alphabets practical to compile.
+        //
         // NOTE: The logic here for iterating over the characters is slightly
         // different from KG's version (I thought this was simpler). He wanted
         // to decr from the head char, but then we're trashing it unnecessarily
         // and need to include extra Incrs to get it back.
+        let mut keyed_by_char: HashMap<u32, &Transition> = HashMap::new();
+        for transition in self {
+            for c in transition.match_pattern.exact_chars() {
+                keyed_by_char.insert(c.to_u32(), transition);
+            }
+        }
 
-        let keyed_by_char: HashMap<AsciiChar, &Transition> = self
-            .iter()
-            .map(|transition| (transition.match_char, transition))
-            .collect();
-
-        // For every char in the range we want to check, if there is a
-        // transition for that char, add code for the transition. For EVERY
-        // char, even ones without transitions, add an Incr so we can progress
-        // to the next char.
-        (0..ALPHABET_SIZE)
-            .map(|c| {
-                // If there is a transition for this char, compile it. If not,
-                // just add an Incr and move on.
-                let mut instrs = Vec::new();
-
-                // This to-char conversion should never fail because we're
-                // only doing this for valid ASCII chars.
-                if let Some(transition) =
-                    keyed_by_char.get(&AsciiChar::from(c).unwrap())
-                {
-                    instrs.append(&mut vec![
-                        Comment(format!("Transition for char={}", c)),
-                        If(transition.compile()),
-                    ]);
-                }
-                instrs.push(InlineComment(
-                    box IncrActive,
-                    format!("Incr for transition char={}", c + 1),
-                ));
-                instrs
+        let mut counter = 0;
+        keyed_by_char
+            .into_iter()
+            .sorted_by_key(|(c, _)| *c)
+            .flat_map(|(c, transition)| {
+                let gap = c - counter;
+                counter = c + 1;
+                iter::repeat(IncrActive).take(gap as usize).chain(vec![
+                    Comment(format!("Transition for char={}", c)),
+                    If(transition.compile(alphabet)),
+                    InlineComment(
+                        box IncrActive,
+                        format!("Incr for transition char={}", c + 1),
+                    ),
+                ])
             })
-            .flatten()
             .collect()
     }
 }
 
 impl Compile for Transition {
-    /// Generates code to execute a transition, which includes one of a L/R/W,
-    /// plus setting the next state.
+    /// Generates code to execute a transition, which includes each tape
+    /// instruction (L/R/W) in order, plus setting the next state.
     ///
     /// After this runs, var_a is reset to 0, and var_i is set to -1 (an invalid
     /// char value) to indicate that the transition executed. Only Incrs will
@@ -398,7 +444,9 @@ impl Compile for Transition {
     /// - Left tape (encoded) [MODIFIED]
     /// - Head char [MODIFIED]
     /// - ...Right tape [MODIFIED]
-    fn compile(&self) -> Vec<SmInstruction> {
+    type Context = Alphabet;
+
+    fn compile(&self, alphabet: &Self::Context) -> Vec<SmInstruction> {
         // Add the write/move/next state code for this transition.
         // This will execute only if the transition char matches the head.
         // Once the If matches, we know var_a = var_i, so we can trash one.
@@ -409,7 +457,11 @@ impl Compile for Transition {
             state_comment!("FREE", "Left tape", ["Head", "...Right tape"]),
         ]
         .into_iter()
-        .chain(self.tape_instruction.compile())
+        .chain(
+            self.tape_instructions
+                .iter()
+                .flat_map(|tape_instruction| tape_instruction.compile(alphabet)),
+        )
         .chain(vec![
             state_comment!(
                 "FREE",
@@ -457,7 +509,10 @@ impl Compile for TapeInstruction {
     /// var_i: Left tape (encoded - MODIFIED)
     /// - Head char (MODIFIED)
     /// - ...Right tape (MODIFIED)
-    fn compile(&self) -> Vec<SmInstruction> {
+    type Context = Alphabet;
+
+    fn compile(&self, alphabet: &Self::Context) -> Vec<SmInstruction> {
+        let size = alphabet.size() as usize;
         match self {
             // Strategy here: Divide left tape by alphabet SIZE by repeated
             // subtracting SIZE until we get negative, then adding it back
@@ -498,7 +553,7 @@ impl Compile for TapeInstruction {
                     // from LT)
                     // - ...Right tape
                     iter::repeat(DecrActive)
-                        .take(ALPHABET_SIZE as usize)
+                        .take(size)
                         .chain(vec![Swap, IncrActive, Swap])
                         .collect(),
                 ),
@@ -510,7 +565,7 @@ impl Compile for TapeInstruction {
             ]
             .into_iter()
             // Add SIZE back to the remainder to get the new Head value
-            .chain(iter::repeat(IncrActive).take(ALPHABET_SIZE as usize))
+            .chain(iter::repeat(IncrActive).take(size))
             // This won't terminate until LT goes negative, so state is:
             // var_a: LT remainder (i.e. NEW head char)
             // var_i: Decr counter (# of times we subtracted SIZE from LT)
@@ -551,7 +606,7 @@ impl Compile for TapeInstruction {
                             PopToActive,
                             PushActive,
                         ])
-                        .take((ALPHABET_SIZE - 1) as usize)
+                        .take(size - 1)
                         .flatten(),
                     )
                     // Now get rid of LT_O and put the head char back in var_a,
@@ -580,7 +635,7 @@ impl Compile for TapeInstruction {
                 ]
                 .into_iter()
                 // Incr up to the new char value, then push it
-                .chain(iter::repeat(IncrActive).take(*c as usize))
+                .chain(iter::repeat(IncrActive).take(c.to_u32() as usize))
                 .chain(vec![PushActive])
                 .collect()
             }
@@ -0,0 +1,296 @@
+use crate::{
+    ast::{MatchPattern, Program, State, Transition, TapeInstruction},
+    validate::{Valid, ValidationOptions},
+};
+use failure::Error;
+use itertools::Itertools;
+
+/// The C boilerplate shared by every generated program: a growable byte
+/// tape (unlike `compile::Compile`'s stack machine, which encodes the left
+/// tape as a single unary-counted integer) plus the read/write helpers that
+/// address it by absolute position, growing and recentering on demand.
+const PRELUDE: &str = r#"#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    unsigned char *cells;
+    size_t capacity;
+    long offset; /* tape position of cells[0] */
+} Tape;
+
+#define TAPE_INITIAL_CAPACITY 1024
+
+static void tape_ensure(Tape *tape, long pos) {
+    if (tape->cells == NULL) {
+        tape->capacity = TAPE_INITIAL_CAPACITY;
+        tape->offset = -(long) (tape->capacity / 2);
+        tape->cells = calloc(tape->capacity, 1);
+        if (tape->cells == NULL) {
+            fprintf(stderr, "out of memory\n");
+            exit(2);
+        }
+    }
+    while (pos < tape->offset || pos >= tape->offset + (long) tape->capacity) {
+        size_t new_capacity = tape->capacity * 2;
+        long new_offset = tape->offset - (long) (new_capacity - tape->capacity) / 2;
+        unsigned char *new_cells = calloc(new_capacity, 1);
+        if (new_cells == NULL) {
+            fprintf(stderr, "out of memory\n");
+            exit(2);
+        }
+        memcpy(new_cells + (tape->offset - new_offset), tape->cells, tape->capacity);
+        free(tape->cells);
+        tape->cells = new_cells;
+        tape->capacity = new_capacity;
+        tape->offset = new_offset;
+    }
+}
+
+static unsigned char tape_read(Tape *tape, long pos) {
+    tape_ensure(tape, pos);
+    return tape->cells[pos - tape->offset];
+}
+
+static void tape_write(Tape *tape, long pos, unsigned char value) {
+    tape_ensure(tape, pos);
+    tape->cells[pos - tape->offset] = value;
+}
+"#;
+
+/// Defines native-codegen steps for a single type, mirroring `compile::Compile`
+/// but targeting a standalone C program instead of the stack machine.
+trait CompileNative {
+    /// Generates the C source fragment for this piece of the program.
+    fn compile_native(&self) -> String;
+}
+
+impl CompileNative for TapeInstruction {
+    fn compile_native(&self) -> String {
+        match self {
+            TapeInstruction::Left => "head--;".to_owned(),
+            TapeInstruction::Right => "head++;".to_owned(),
+            TapeInstruction::Write(c) => format!("tape_write(&tape, head, {});", c.to_u32()),
+        }
+    }
+}
+
+impl CompileNative for Transition {
+    /// The body that runs once this transition is chosen: its tape
+    /// instructions in order, then the jump to its next state.
+    fn compile_native(&self) -> String {
+        self.tape_instructions
+            .iter()
+            .map(TapeInstruction::compile_native)
+            .chain(vec![format!("state = {};", self.next_state), "continue;".to_owned()])
+            .join(" ")
+    }
+}
+
+impl CompileNative for [Transition] {
+    /// An if/else-if chain over this state's transitions: `Exact`/`AnyOf`
+    /// entries are checked by equality against the head char, and a
+    /// `Wildcard` (there's at most one per state, per `Validate for State`)
+    /// is a trailing catch-all `else`, consistent with it being a lowest-
+    /// priority fallback (see `ast::MatchPattern::Wildcard`). A char that
+    /// matches nothing falls all the way through, leaving no `else` to run.
+    fn compile_native(&self) -> String {
+        let mut wildcard = None;
+        let mut branches = Vec::new();
+        for transition in self {
+            match &transition.match_pattern {
+                MatchPattern::Wildcard => wildcard = Some(transition),
+                pattern => {
+                    let condition = pattern
+                        .exact_chars()
+                        .iter()
+                        .map(|c| format!("head_char == {}", c.to_u32()))
+                        .join(" || ");
+                    branches.push(format!("if ({}) {{ {} }}", condition, transition.compile_native()));
+                }
+            }
+        }
+        if let Some(transition) = wildcard {
+            branches.push(format!("{{ {} }}", transition.compile_native()));
+        }
+        branches.join(" else ")
+    }
+}
+
+impl CompileNative for State {
+    /// A `case <id>:` block: read the head char (unless there are no
+    /// transitions to check it against), try each transition in turn, and
+    /// fall back to printing ACCEPT/REJECT and returning if none match,
+    /// exactly mirroring the HALT encoding in `compile::Compile for State`.
+    fn compile_native(&self) -> String {
+        let halt = if self.accepting {
+            "printf(\"ACCEPT\\n\"); return 0;"
+        } else {
+            "printf(\"REJECT\\n\"); return 1;"
+        };
+        if self.transitions.is_empty() {
+            format!("        case {}: {{ {} }}\n", self.id, halt)
+        } else {
+            format!(
+                "        case {}: {{\n            unsigned char head_char = tape_read(&tape, head);\n            {}\n            {}\n        }}\n",
+                self.id,
+                self.transitions.compile_native(),
+                halt,
+            )
+        }
+    }
+}
+
+impl CompileNative for Valid<Program> {
+    /// A complete, standalone C program: the tape helpers, a `main` that
+    /// reads stdin onto the tape left to right starting at position 0, then
+    /// a `switch (state)` loop dispatching to each state's `case` block.
+    ///
+    /// Unlike `compile::Compile`'s stack machine, this doesn't need the
+    /// input reversed - the tape is addressed directly by position instead
+    /// of being packed into a single encoded integer, so there's no reason
+    /// to read it backwards.
+    fn compile_native(&self) -> String {
+        let initial_state = self
+            .states
+            .iter()
+            .find(|state| state.initial)
+            .expect("No initial state defined! Something went wrong in validation.");
+
+        let cases: String = self
+            .states
+            .iter()
+            .sorted_by_key(|state| state.id)
+            .map(State::compile_native)
+            .collect();
+
+        format!(
+            "{prelude}\nint main(void) {{\n    Tape tape = {{ NULL, 0, 0 }};\n    long head = 0;\n    int state = {initial};\n\n    {{\n        int c;\n        long pos = 0;\n        while ((c = getchar()) != EOF) {{\n            tape_write(&tape, pos, (unsigned char) c);\n            pos++;\n        }}\n    }}\n\n    for (;;) {{\n        switch (state) {{\n{cases}        default:\n            fprintf(stderr, \"invalid state %d\\n\", state);\n            return 2;\n        }}\n    }}\n}}\n",
+            prelude = PRELUDE,
+            initial = initial_state.id,
+            cases = cases,
+        )
+    }
+}
+
+/// Compiles `program` directly to a standalone C program using a real
+/// growable byte tape and a `switch`-dispatched main loop, instead of
+/// `TuringMachine`'s two-variable stack machine. This trades the stack
+/// machine's single Rocketlang-compatible target for native execution
+/// speed: the output is a normal C file that any C compiler can turn into
+/// a binary that runs the machine directly.
+pub fn compile_native(program: Program) -> Result<String, Error> {
+    let program = program.validate_into(&ValidationOptions::default())?;
+    Ok(program.compile_native())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Alphabet, Char};
+
+    /// Machine that matches the single char 'x'.
+    fn x_machine() -> Program {
+        Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('x')),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 2,
+                    }],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        }
+    }
+
+    #[test]
+    fn test_compiles_states_in_order() {
+        let source = compile_native(x_machine()).unwrap();
+        let first_case = source.find("case 1:").unwrap();
+        let second_case = source.find("case 2:").unwrap();
+        assert!(first_case < second_case);
+    }
+
+    #[test]
+    fn test_transition_checks_head_and_jumps() {
+        let source = compile_native(x_machine()).unwrap();
+        assert!(source.contains(&format!("head_char == {}", 'x' as u32)));
+        assert!(source.contains("head++;"));
+        assert!(source.contains("state = 2;"));
+    }
+
+    #[test]
+    fn test_halting_states_print_outcome_and_return() {
+        let source = compile_native(x_machine()).unwrap();
+        assert!(source.contains("printf(\"REJECT\\n\"); return 1;"));
+        assert!(source.contains("printf(\"ACCEPT\\n\"); return 0;"));
+    }
+
+    #[test]
+    fn test_wildcard_is_trailing_else() {
+        let source = compile_native(Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 1,
+                    },
+                    Transition {
+                        match_pattern: MatchPattern::Wildcard,
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 1,
+                    },
+                ],
+            }],
+            alphabet: Alphabet::default(),
+        })
+        .unwrap();
+        let if_pos = source.find("if (head_char ==").unwrap();
+        let else_pos = source.find("} else {").unwrap();
+        assert!(if_pos < else_pos);
+    }
+
+    #[test]
+    fn test_invalid_program_is_an_error() {
+        assert!(compile_native(Program {
+            states: vec![],
+            alphabet: Alphabet::default(),
+        })
+        .is_err());
+    }
+
+    /// The generated C tape is `unsigned char`-addressed (see `PRELUDE`), so
+    /// an alphabet wider than 8 bits would silently truncate `tape_write`/
+    /// `head_char` comparisons instead of erroring. `compile_native` goes
+    /// through the same `validate_into` every other backend does, so this
+    /// is rejected before any C is generated - see
+    /// `CompilerErrorKind::AlphabetTooWide`.
+    #[test]
+    fn test_wide_alphabet_is_rejected() {
+        let result = compile_native(Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![],
+            }],
+            alphabet: Alphabet { char_bits: 9 },
+        });
+        assert!(result.is_err());
+    }
+}
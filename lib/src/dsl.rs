@@ -0,0 +1,363 @@
+use crate::{
+    ast::{Alphabet, Char, MatchPattern, Program, State, StateId, TapeInstruction, Transition},
+    error::{CompilerError, CompilerErrorKind, CompilerErrors},
+};
+use failure::Error;
+use std::collections::HashMap;
+
+/// Parses the compact, section-based TM description format:
+/// ```text
+/// STATES: [a], b, (f)
+/// SYMBOLS: 0, 1
+/// TRANSITIONS:
+/// a, 0, right, b
+/// a, 1, left, a
+/// b, 0, write 1; right, f
+/// ```
+/// `STATES:` lists every state by name, in the order their numeric
+/// `StateId`s are assigned (starting at 1). `[name]` marks the initial
+/// state, `(name)` marks an accepting state, and the two can combine in
+/// either order (`[(name)]`/`([name])`) for a state that's both.
+/// `SYMBOLS:` lists every character in the alphabet (same literal syntax as
+/// `match`/`write` below); the resulting `Alphabet` is sized to the
+/// smallest power of two that fits them all, or defaults to
+/// `Alphabet::default()` if the section is omitted.
+/// Each `TRANSITIONS:` line is `state, match, instructions, next_state`,
+/// where `match` is one `<char>` (matches that char exactly), `<char> |
+/// <char> | ...` (matches any of them), or `*` (matches every char in the
+/// alphabet); `instructions` is one or more of `left`, `right`, or
+/// `write <char>`, separated by `;` and executed in order; and
+/// `state`/`next_state` are names from `STATES:`. Blank lines and lines
+/// starting with `#` are ignored everywhere.
+pub fn parse(src: &str) -> Result<Program, Error> {
+    let mut errors = Vec::new();
+    let mut state_order: Vec<(String, bool, bool)> = Vec::new();
+    let mut symbols: Vec<Char> = Vec::new();
+    let mut symbols_declared = false;
+    let mut transition_lines: Vec<(usize, &str)> = Vec::new();
+    let mut in_transitions = false;
+
+    for (i, raw_line) in src.lines().enumerate() {
+        let line_num = i + 1;
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("STATES:") {
+            in_transitions = false;
+            parse_states_list(rest, line_num, &mut state_order, &mut errors);
+        } else if let Some(rest) = line.strip_prefix("SYMBOLS:") {
+            in_transitions = false;
+            symbols_declared = true;
+            parse_symbols_list(rest, line_num, &mut symbols, &mut errors);
+        } else if line == "TRANSITIONS:" {
+            in_transitions = true;
+        } else if in_transitions {
+            transition_lines.push((line_num, line));
+        } else {
+            errors.push(CompilerErrorKind::MalformedDslLine(line_num, line.to_owned()).into());
+        }
+    }
+
+    let mut symbol_table: HashMap<&str, StateId> = HashMap::new();
+    for (idx, (name, _, _)) in state_order.iter().enumerate() {
+        if symbol_table.insert(name.as_str(), idx + 1).is_some() {
+            errors.push(CompilerErrorKind::DuplicateStateName(name.clone()).into());
+        }
+    }
+
+    let mut transitions_by_id: HashMap<StateId, Vec<Transition>> = HashMap::new();
+    for (line_num, line) in transition_lines {
+        let parts: Vec<&str> = line.split(',').map(str::trim).collect();
+        if parts.len() != 4 {
+            errors.push(CompilerErrorKind::MalformedDslLine(line_num, line.to_owned()).into());
+            continue;
+        }
+
+        let state_id = match symbol_table.get(parts[0]) {
+            Some(&id) => id,
+            None => {
+                errors.push(
+                    CompilerErrorKind::UndefinedStateName(line_num, parts[0].to_owned()).into(),
+                );
+                continue;
+            }
+        };
+        let match_pattern = match parse_dsl_match_pattern(parts[1], line_num) {
+            Ok(p) => p,
+            Err(e) => {
+                errors.push(e.into());
+                continue;
+            }
+        };
+        let mut tape_instructions = Vec::new();
+        let mut instruction_had_error = false;
+        for instr in parts[2].split(';') {
+            match parse_dsl_instruction(instr.trim(), line_num) {
+                Ok(i) => tape_instructions.push(i),
+                Err(e) => {
+                    errors.push(e.into());
+                    instruction_had_error = true;
+                }
+            }
+        }
+        if instruction_had_error {
+            continue;
+        }
+        let next_state = match symbol_table.get(parts[3]) {
+            Some(&id) => id,
+            None => {
+                errors.push(
+                    CompilerErrorKind::UndefinedStateName(line_num, parts[3].to_owned()).into(),
+                );
+                continue;
+            }
+        };
+
+        transitions_by_id
+            .entry(state_id)
+            .or_default()
+            .push(Transition {
+                match_pattern,
+                tape_instructions,
+                next_state,
+            });
+    }
+
+    if !errors.is_empty() {
+        return Err(CompilerErrors::new(errors).into());
+    }
+
+    let alphabet = if symbols_declared {
+        let max_symbol = symbols.iter().map(Char::to_u32).max().unwrap_or(0);
+        let mut char_bits = 0;
+        while (1u32 << char_bits) <= max_symbol {
+            char_bits += 1;
+        }
+        Alphabet { char_bits }
+    } else {
+        Alphabet::default()
+    };
+
+    let states = state_order
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (_, initial, accepting))| {
+            let id = idx + 1;
+            State {
+                id,
+                initial,
+                accepting,
+                transitions: transitions_by_id.remove(&id).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(Program { states, alphabet })
+}
+
+/// Parses the comma-separated `STATES:` list, handling the `[...]`
+/// (initial) and `(...)` (accepting) wrappers in either nesting order.
+fn parse_states_list(
+    rest: &str,
+    line_num: usize,
+    out: &mut Vec<(String, bool, bool)>,
+    errors: &mut Vec<CompilerError>,
+) {
+    for token in rest.split(',') {
+        let original = token.trim();
+        let mut name = original;
+        let mut initial = false;
+        let mut accepting = false;
+        loop {
+            if let Some(inner) = name.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                initial = true;
+                name = inner.trim();
+                continue;
+            }
+            if let Some(inner) = name.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+                accepting = true;
+                name = inner.trim();
+                continue;
+            }
+            break;
+        }
+
+        if name.is_empty() {
+            errors.push(CompilerErrorKind::MalformedDslLine(line_num, original.to_owned()).into());
+            continue;
+        }
+        out.push((name.to_owned(), initial, accepting));
+    }
+}
+
+/// Parses the comma-separated `SYMBOLS:` list into `Char`s.
+fn parse_symbols_list(
+    rest: &str,
+    line_num: usize,
+    out: &mut Vec<Char>,
+    errors: &mut Vec<CompilerError>,
+) {
+    for token in rest.split(',') {
+        let token = token.trim();
+        if token.is_empty() {
+            continue;
+        }
+        match parse_dsl_char(token, line_num) {
+            Ok(c) => out.push(c),
+            Err(e) => errors.push(e.into()),
+        }
+    }
+}
+
+/// Parses either a bare number (`Char::Num`) or a single quoted character
+/// (`Char::Codepoint`), e.g. `0` or `'f'`.
+fn parse_dsl_char(s: &str, line_num: usize) -> Result<Char, CompilerErrorKind> {
+    if let Some(inner) = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        let mut chars = inner.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Char::Codepoint(c)),
+            _ => Err(CompilerErrorKind::InvalidDslChar(line_num, s.to_owned())),
+        };
+    }
+
+    s.parse()
+        .map(Char::Num)
+        .map_err(|_| CompilerErrorKind::InvalidDslChar(line_num, s.to_owned()))
+}
+
+/// Parses a transition's `match` field: `*` (`Wildcard`), a single `<char>`
+/// (`Exact`), or `<char> | <char> | ...` (`AnyOf`).
+fn parse_dsl_match_pattern(s: &str, line_num: usize) -> Result<MatchPattern, CompilerErrorKind> {
+    if s == "*" {
+        return Ok(MatchPattern::Wildcard);
+    }
+
+    let mut chars = s
+        .split('|')
+        .map(|part| parse_dsl_char(part.trim(), line_num))
+        .collect::<Result<Vec<_>, _>>()?;
+    if chars.len() == 1 {
+        Ok(MatchPattern::Exact(chars.remove(0)))
+    } else {
+        Ok(MatchPattern::AnyOf(chars))
+    }
+}
+
+/// Parses `left`, `right`, or `write <char>` into a `TapeInstruction`.
+fn parse_dsl_instruction(s: &str, line_num: usize) -> Result<TapeInstruction, CompilerErrorKind> {
+    match s {
+        "left" => Ok(TapeInstruction::Left),
+        "right" => Ok(TapeInstruction::Right),
+        _ => {
+            let char_str = s
+                .strip_prefix("write")
+                .map(str::trim_start)
+                .ok_or_else(|| CompilerErrorKind::InvalidDslInstruction(line_num, s.to_owned()))?;
+            Ok(TapeInstruction::Write(parse_dsl_char(char_str, line_num)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        utils::assert_error,
+        validate::{Validate, ValidationOptions},
+    };
+
+    const BINARY_INCREMENT_SRC: &str = "
+        # Skips right over 1s and accepts at the first 0; just exercises the grammar end to end.
+        STATES: [a], (f)
+        SYMBOLS: 0, 1
+        TRANSITIONS:
+        a, 0, right, f
+        a, 1, right, a
+    ";
+
+    #[test]
+    fn test_parses_sections() {
+        let program = parse(BINARY_INCREMENT_SRC).unwrap();
+        assert_eq!(program.states.len(), 2);
+        assert_eq!(program.states[0].id, 1);
+        assert!(program.states[0].initial);
+        assert!(!program.states[0].accepting);
+        assert_eq!(program.states[0].transitions.len(), 2);
+        assert_eq!(program.states[1].id, 2);
+        assert!(!program.states[1].initial);
+        assert!(program.states[1].accepting);
+        // 0 and 1 both fit in a single bit.
+        assert_eq!(program.alphabet.char_bits, 1);
+    }
+
+    #[test]
+    fn test_parsed_program_validates() {
+        let program = parse(BINARY_INCREMENT_SRC).unwrap();
+        program
+            .validate_into(&ValidationOptions::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_defaults_to_ascii_alphabet_without_symbols_section() {
+        let program = parse("STATES: [(a)]\nTRANSITIONS:\n").unwrap();
+        assert_eq!(program.alphabet, Alphabet::default());
+    }
+
+    #[test]
+    fn test_undefined_state_name_error() {
+        assert_error(
+            "undefined state name \"z\"",
+            parse("STATES: [a]\nTRANSITIONS:\na, 0, right, z"),
+        );
+    }
+
+    #[test]
+    fn test_malformed_transition_line_error() {
+        assert_error(
+            "malformed DSL line",
+            parse("STATES: [a]\nTRANSITIONS:\na, 0, right"),
+        );
+    }
+
+    #[test]
+    fn test_multi_instruction_transition() {
+        let program = parse("STATES: [(a)]\nTRANSITIONS:\na, 0, write 1; right; left, a").unwrap();
+        assert_eq!(
+            program.states[0].transitions[0].tape_instructions,
+            vec![
+                TapeInstruction::Write(Char::Num(1)),
+                TapeInstruction::Right,
+                TapeInstruction::Left,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wildcard_match_pattern() {
+        let program = parse("STATES: [(a)]\nTRANSITIONS:\na, *, right, a").unwrap();
+        assert_eq!(
+            program.states[0].transitions[0].match_pattern,
+            MatchPattern::Wildcard
+        );
+    }
+
+    #[test]
+    fn test_any_of_match_pattern() {
+        let program = parse("STATES: [(a)]\nTRANSITIONS:\na, 0 | 1, right, a").unwrap();
+        assert_eq!(
+            program.states[0].transitions[0].match_pattern,
+            MatchPattern::AnyOf(vec![Char::Num(0), Char::Num(1)])
+        );
+    }
+
+    #[test]
+    fn test_invalid_instruction_error() {
+        assert_error(
+            "invalid tape instruction",
+            parse("STATES: [a]\nTRANSITIONS:\na, 0, sideways, a"),
+        );
+    }
+}
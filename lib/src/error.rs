@@ -1,11 +1,34 @@
-use crate::ast::StateId;
-use failure::{ Fail};
+use crate::{
+    ast::{Char, StateId},
+    stack::SmFault,
+    utils::CharLocation,
+};
+use failure::Fail;
 use itertools::Itertools;
 use std::fmt::{self, Display, Formatter};
 use std::ops::Deref;
 
+/// One segment in the structural path to where a `CompilerError` occurred,
+/// e.g. a state's third transition renders as `[State(1), Transition(2)]`.
+/// `Validate` impls accumulate this path as they recurse from `Program` down
+/// through `State` and `Transition`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    State(StateId),
+    Transition(usize),
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::State(id) => write!(f, "state {}", id),
+            Self::Transition(index) => write!(f, "transition {}", index),
+        }
+    }
+}
+
 #[derive(Debug, Fail)]
-pub enum CompilerError {
+pub enum CompilerErrorKind {
     #[fail(display = "Invalid state ID: {}. Must be >0.", 0)]
     InvalidStateId(StateId),
     #[fail(display = "State ID defined multiple times: {}", 0)]
@@ -16,8 +39,90 @@ pub enum CompilerError {
     MultipleInitialStates(Vec<StateId>),
     #[fail(display = "Undefined state: {}", 0)]
     UndefinedState(StateId),
-    #[fail(display = "Invalid character: {}", 0)]
-    InvalidCharacter(char),
+    #[fail(
+        display = "illegal character {} at {}: blank or not in alphabet of size {}",
+        value, location, alphabet_size
+    )]
+    IllegalCharacter {
+        value: Char,
+        location: CharLocation,
+        alphabet_size: u32,
+    },
+    #[fail(display = "Line {}: malformed DSL line: {:?}", 0, 1)]
+    MalformedDslLine(usize, String),
+    #[fail(display = "Duplicate state name in STATES: {:?}", 0)]
+    DuplicateStateName(String),
+    #[fail(display = "Line {}: undefined state name {:?}", 0, 1)]
+    UndefinedStateName(usize, String),
+    #[fail(display = "Line {}: invalid character literal {:?}", 0, 1)]
+    InvalidDslChar(usize, String),
+    #[fail(display = "Line {}: invalid tape instruction {:?}", 0, 1)]
+    InvalidDslInstruction(usize, String),
+    #[fail(display = "Unreachable state: {}", 0)]
+    UnreachableState(StateId),
+    #[fail(display = "Dead state (cannot reach an accepting state): {}", 0)]
+    DeadState(StateId),
+    #[fail(
+        display = "State {} has multiple transitions matching character {}",
+        state, match_char
+    )]
+    ConflictingTransition { state: StateId, match_char: Char },
+    #[fail(display = "State {} has multiple wildcard transitions", 0)]
+    DuplicateWildcardTransition(StateId),
+    #[fail(
+        display = "State {} has no transition for character {}",
+        state, match_char
+    )]
+    IncompleteState { state: StateId, match_char: Char },
+    #[fail(
+        display = "Alphabet char_bits {} exceeds the 8-bit limit the stack \
+                   machine's byte-oriented I/O supports",
+        0
+    )]
+    AlphabetTooWide(u32),
+}
+
+/// A `CompilerErrorKind` plus the structural path (which state, which
+/// transition, ...) that produced it. Wrapping every error this way, rather
+/// than baking location into each variant ad hoc, is what lets errors on
+/// non-trivial programs say e.g. "in state 1, transition 2: Undefined state:
+/// 3" instead of just "Undefined state: 3".
+#[derive(Debug, Fail)]
+pub struct CompilerError {
+    path: Vec<PathSegment>,
+    kind: CompilerErrorKind,
+}
+
+impl CompilerError {
+    /// Attaches a structural path to a `CompilerErrorKind`.
+    pub fn at(kind: CompilerErrorKind, path: Vec<PathSegment>) -> Self {
+        CompilerError { path, kind }
+    }
+
+    /// Returns this error with its path replaced.
+    pub fn with_path(mut self, path: Vec<PathSegment>) -> Self {
+        self.path = path;
+        self
+    }
+}
+
+impl From<CompilerErrorKind> for CompilerError {
+    fn from(kind: CompilerErrorKind) -> Self {
+        CompilerError {
+            path: Vec::new(),
+            kind,
+        }
+    }
+}
+
+impl Display for CompilerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "in {}: {}", self.path.iter().join(", "), self.kind)
+        }
+    }
 }
 
 // Container for holding multiple compiler errors. This is the most common way
@@ -43,3 +148,26 @@ impl Deref for CompilerErrors {
         &self.0
     }
 }
+
+/// Errors that can occur while actually *executing* a compiled machine, as
+/// opposed to `CompilerError`, which covers mistakes in the machine
+/// definition itself.
+#[derive(Debug, Fail)]
+pub enum RuntimeError {
+    #[fail(display = "{}", 0)]
+    InvalidTapeChar(CompilerError),
+    #[fail(display = "{}", 0)]
+    MachineFault(SmFault),
+}
+
+impl From<SmFault> for RuntimeError {
+    fn from(fault: SmFault) -> Self {
+        RuntimeError::MachineFault(fault)
+    }
+}
+
+impl From<CompilerError> for RuntimeError {
+    fn from(error: CompilerError) -> Self {
+        RuntimeError::InvalidTapeChar(error)
+    }
+}
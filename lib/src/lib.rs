@@ -1,13 +1,35 @@
 #![feature(box_syntax)]
 
 mod ast;
+mod bytecode;
 mod compile;
+mod compile_native;
+mod dsl;
 mod error;
+mod optimize;
+mod parser;
 mod rocketlang;
 mod stack;
+mod termination;
 mod turing;
 mod utils;
 mod validate;
 
 pub use ast::Program;
-pub use turing::TuringMachine;
+pub use bytecode::{
+    compile_bytecode, execute as execute_bytecode, parse_bytecode, Bytecode,
+    BytecodeExecError, BytecodeParseError,
+};
+pub use compile_native::compile_native;
+pub use dsl::parse as parse_dsl;
+pub use parser::{parse_program, ProgramParseError};
+pub use rocketlang::{
+    execute as execute_rocketlang, RocketlangError, RocketlangExecError,
+};
+pub use stack::{
+    Breakpoint, ProfileReport, Profiler, RunUntil, SmFault, SmInstruction,
+    Snapshot, StackMachine, StepOutcome, Stepper,
+};
+pub use termination::{analyze_termination, Termination};
+pub use turing::{Outcome, TmSnapshot, TuringMachine};
+pub use validate::{Valid, Validate, ValidationOptions};
@@ -0,0 +1,350 @@
+use crate::stack::SmInstruction::{self, *};
+
+/// The statically-known value of one stack machine register (`var_a` or
+/// `var_i`) at some point in the instruction stream, as tracked by the
+/// abstract interpreter below.
+///
+/// - `Bottom` means no control-flow path reaches this point at all. It only
+///   ever shows up as the identity element while folding a `join` over the
+///   incoming edges of a block; it never survives into a finished analysis.
+/// - `Known(v)` means every path that can reach this point agrees that the
+///   register holds exactly `v`.
+/// - `Top` means the value is unconstrained, either because it's genuinely
+///   data-dependent (e.g. right after a `ReadToActive`) or because two
+///   incoming paths disagreed and we gave up tracking it precisely.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Abs {
+    Bottom,
+    Known(i64),
+    Top,
+}
+
+impl Abs {
+    /// Combines the value coming out of two different control-flow edges
+    /// into the value that holds once they merge back together.
+    fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (Abs::Bottom, x) | (x, Abs::Bottom) => x,
+            (Abs::Known(a), Abs::Known(b)) if a == b => Abs::Known(a),
+            _ => Abs::Top,
+        }
+    }
+
+    fn incr(self) -> Self {
+        match self {
+            Abs::Known(v) => Abs::Known(v + 1),
+            other => other,
+        }
+    }
+
+    fn decr(self) -> Self {
+        match self {
+            Abs::Known(v) => Abs::Known(v - 1),
+            other => other,
+        }
+    }
+}
+
+/// The abstract machine state (both registers) at some point in the stream.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct State {
+    active: Abs,
+    inactive: Abs,
+}
+
+impl State {
+    /// The real state of a freshly-constructed `StackMachine`: both
+    /// registers start at 0.
+    fn initial() -> Self {
+        State {
+            active: Abs::Known(0),
+            inactive: Abs::Known(0),
+        }
+    }
+
+    /// A state about which nothing is known, used as the entry state for
+    /// loop bodies that may run any number of times.
+    fn top() -> Self {
+        State {
+            active: Abs::Top,
+            inactive: Abs::Top,
+        }
+    }
+
+    fn join(self, other: Self) -> Self {
+        State {
+            active: self.active.join(other.active),
+            inactive: self.inactive.join(other.inactive),
+        }
+    }
+}
+
+/// Whether an `If`'s condition (`active_var == inactive_var`) is statically
+/// decidable given `state`.
+fn if_condition(state: &State) -> Option<bool> {
+    match (state.active, state.inactive) {
+        (Abs::Known(a), Abs::Known(b)) => Some(a == b),
+        _ => None,
+    }
+}
+
+/// Applies the effect of a single non-block instruction (i.e. anything
+/// besides `If`/`While`, which `rewrite_block` handles directly) to `state`.
+fn apply_effect(state: State, instr: &SmInstruction) -> State {
+    match instr {
+        IncrActive => State {
+            active: state.active.incr(),
+            ..state
+        },
+        DecrActive => State {
+            active: state.active.decr(),
+            ..state
+        },
+        Swap => State {
+            active: state.inactive,
+            inactive: state.active,
+        },
+        SaveActive => State {
+            inactive: state.active,
+            ..state
+        },
+        PopToActive | ReadToActive => State {
+            active: Abs::Top,
+            ..state
+        },
+        InlineComment(inner, _) => apply_effect(state, inner),
+        PushZero | PushActive | ToggleErrors | PrintActive | PrintState | Comment(_)
+        | DebugPrint(..) => state,
+        If(_) | While(_) => unreachable!("handled directly in rewrite_block"),
+    }
+}
+
+/// Returns the number of leading instructions in `rest` that form a
+/// value-independent no-op pair (cancelling Incr/Decr, a double Swap, or a
+/// push immediately undone by a pop), or `None` if `rest` doesn't start with
+/// one.
+fn noop_pair(rest: &[SmInstruction]) -> Option<usize> {
+    if rest.len() < 2 {
+        return None;
+    }
+    match (&rest[0], &rest[1]) {
+        (IncrActive, DecrActive)
+        | (DecrActive, IncrActive)
+        | (Swap, Swap)
+        | (PushActive, PopToActive) => Some(2),
+        _ => None,
+    }
+}
+
+/// Runs one simplification pass over `instrs`, entering `instrs` with the
+/// abstract state `start`. Returns the rewritten instructions, the abstract
+/// state on exit from the block, and whether anything changed.
+fn rewrite_block(instrs: &[SmInstruction], start: State) -> (Vec<SmInstruction>, State, bool) {
+    let mut out = Vec::with_capacity(instrs.len());
+    let mut state = start;
+    let mut changed = false;
+    let mut i = 0;
+
+    while i < instrs.len() {
+        // Rule 1: cancelling adjacent ops, regardless of what we know about
+        // the registers.
+        if let Some(skip) = noop_pair(&instrs[i..]) {
+            i += skip;
+            changed = true;
+            continue;
+        }
+
+        // Rule 2: `PushZero, PopToActive` is a no-op reset if var_a is
+        // already known to be 0.
+        if matches!(instrs[i], PushZero)
+            && matches!(instrs.get(i + 1), Some(PopToActive))
+            && state.active == Abs::Known(0)
+        {
+            i += 2;
+            changed = true;
+            continue;
+        }
+
+        match &instrs[i] {
+            If(body) => match if_condition(&state) {
+                // Never taken: drop the whole block.
+                Some(false) => changed = true,
+                // Always taken: inline the body, folding with the same
+                // (exact) entry state.
+                Some(true) => {
+                    changed = true;
+                    let (new_body, exit, _) = rewrite_block(body, state);
+                    out.extend(new_body);
+                    state = exit;
+                }
+                // Can't decide: keep the If, but still optimize its body,
+                // and the state after is whatever the taken and not-taken
+                // edges agree on.
+                None => {
+                    let (new_body, exit, body_changed) = rewrite_block(body, state);
+                    changed |= body_changed;
+                    state = exit.join(state);
+                    out.push(If(new_body));
+                }
+            },
+            While(body) => {
+                if matches!(state.active, Abs::Known(v) if v <= 0) {
+                    // Condition is false before the first check, so the
+                    // loop never runs at all.
+                    changed = true;
+                } else {
+                    // The loop may run any number of times, so the entry
+                    // state can't be assumed to hold past the first
+                    // iteration. Only value-independent rewrites (Rule 1,
+                    // and provable sub-blocks) are safe inside the body.
+                    let (new_body, _, body_changed) = rewrite_block(body, State::top());
+                    changed |= body_changed;
+                    out.push(While(new_body));
+                    state = State::top();
+                }
+            }
+            other => {
+                state = apply_effect(state, other);
+                out.push(other.clone());
+            }
+        }
+        i += 1;
+    }
+
+    (out, state, changed)
+}
+
+/// Simplifies a compiled instruction stream, rewriting it to a fixed point.
+/// This is purely a size/speed optimization: the result always behaves
+/// identically to the input when run on a [`StackMachine`](crate::StackMachine).
+///
+/// The `Compile` implementations emit a lot of structurally-necessary but
+/// frequently redundant code (e.g. a `PushZero, PopToActive` reset after
+/// every transition, even when the register is already 0), since they're
+/// written to be correct for arbitrary preceding state rather than to track
+/// what's actually true at each point. This runs a small abstract
+/// interpreter over the stream (tracking `var_a`/`var_i` as
+/// unknown/known-constant) alongside a peephole pass, and repeats until
+/// nothing more can be simplified.
+pub fn optimize(instructions: Vec<SmInstruction>) -> Vec<SmInstruction> {
+    let mut instructions = instructions;
+    loop {
+        let (next, _, changed) = rewrite_block(&instructions, State::initial());
+        instructions = next;
+        if !changed {
+            return instructions;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::{
+            Alphabet, Char, MatchPattern, Program, State as AstState, TapeInstruction, Transition,
+        },
+        compile::Compile,
+        stack::StackMachine,
+        validate::{Validate, ValidationOptions},
+    };
+
+    /// A small two-state machine that accepts any input whose head char is
+    /// 'f' and rejects everything else.
+    fn compile_test_program() -> Vec<SmInstruction> {
+        Program {
+            states: vec![
+                AstState {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('f')),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 2,
+                    }],
+                },
+                AstState {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default())
+        .unwrap()
+        .compile(&())
+    }
+
+    fn run(instructions: &[SmInstruction], input: &str) -> Vec<u8> {
+        let mut machine = StackMachine::new();
+        let mut output = Vec::new();
+        machine
+            .run(input.as_bytes(), &mut output, instructions)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn test_optimize_shrinks_stream() {
+        let original = compile_test_program();
+        let optimized = optimize(original.clone());
+        assert!(
+            optimized.len() < original.len(),
+            "expected optimization to shrink the stream ({} -> {})",
+            original.len(),
+            optimized.len()
+        );
+    }
+
+    #[test]
+    fn test_optimize_preserves_behavior_on_match() {
+        let original = compile_test_program();
+        let optimized = optimize(original.clone());
+        assert_eq!(run(&original, "f"), run(&optimized, "f"));
+    }
+
+    #[test]
+    fn test_optimize_preserves_behavior_on_mismatch() {
+        let original = compile_test_program();
+        let optimized = optimize(original.clone());
+        assert_eq!(run(&original, "x"), run(&optimized, "x"));
+    }
+
+    #[test]
+    fn test_cancelling_incr_decr_removed() {
+        let (out, state, changed) =
+            rewrite_block(&[IncrActive, DecrActive, IncrActive], State::top());
+        assert!(changed);
+        assert_eq!(out, vec![IncrActive]);
+        assert_eq!(state.active, Abs::Top);
+    }
+
+    #[test]
+    fn test_redundant_reset_removed() {
+        let (out, _, changed) = rewrite_block(&[PushZero, PopToActive], State::initial());
+        assert!(changed);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_dead_if_dropped() {
+        // var_a=1, var_i=0 going in, so active != inactive: never taken.
+        let (out, _, changed) = rewrite_block(
+            &[IncrActive, If(vec![IncrActive, IncrActive])],
+            State::initial(),
+        );
+        assert!(changed);
+        assert_eq!(out, vec![IncrActive]);
+    }
+
+    #[test]
+    fn test_dead_while_dropped() {
+        // var_a starts at 0, so the While condition (var_a > 0) is false.
+        let (out, _, changed) = rewrite_block(&[While(vec![IncrActive])], State::initial());
+        assert!(changed);
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+use crate::ast::{Alphabet, Char, MatchPattern, Program, State, TapeInstruction, Transition};
+use failure::Fail;
+
+/// Parses a concise textual Turing-machine description into a `Program`,
+/// the inverse of hand-constructing `ast` structs in Rust. This is the
+/// crate's ".tm file" format.
+///
+/// Grammar, one directive per line:
+/// ```text
+/// state <id> [initial] [accepting]
+/// on <pattern> -> <instr> [, <instr> ...] , goto <id>
+/// ```
+/// where each `<instr>` is `left`, `right`, or `write <char>`. Transition
+/// lines (`on ...`) apply to whichever `state` line preceded them, and their
+/// instructions execute in order before jumping to `goto`'s state. `<char>`
+/// is either a bare number (`0`, `42`) or a single quoted character (`'f'`),
+/// matching `ast::Char`'s two variants. `<pattern>` is one `<char>` (matches
+/// that char exactly), `<char> | <char> | ...` (matches any of them), or `*`
+/// (matches every char in the alphabet), matching `ast::MatchPattern`'s three
+/// variants. Blank lines and lines starting with `#` are ignored. The
+/// alphabet is not configurable from this format; parsed programs always use
+/// `Alphabet::default()`.
+pub fn parse_program(src: &str) -> Result<Program, ProgramParseError> {
+    let mut states: Vec<State> = Vec::new();
+
+    for (i, raw_line) in src.lines().enumerate() {
+        let line_num = i + 1;
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("state") => states.push(parse_state_header(tokens, line_num)?),
+            Some("on") => {
+                let state = states
+                    .last_mut()
+                    .ok_or(ProgramParseError::TransitionBeforeState(line_num))?;
+                state.transitions.push(parse_transition(line, line_num)?);
+            }
+            _ => {
+                return Err(ProgramParseError::UnrecognizedLine(
+                    line_num,
+                    line.to_owned(),
+                ))
+            }
+        }
+    }
+
+    Ok(Program {
+        states,
+        alphabet: Alphabet::default(),
+    })
+}
+
+/// Parses a `state <id> [initial] [accepting]` line, given the tokens after
+/// the leading `state` keyword.
+fn parse_state_header<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    line_num: usize,
+) -> Result<State, ProgramParseError> {
+    let id_str = tokens
+        .next()
+        .ok_or(ProgramParseError::MissingStateId(line_num))?;
+    let id = id_str
+        .parse()
+        .map_err(|_| ProgramParseError::InvalidStateId(line_num, id_str.to_owned()))?;
+
+    let mut initial = false;
+    let mut accepting = false;
+    for token in tokens {
+        match token {
+            "initial" => initial = true,
+            "accepting" => accepting = true,
+            other => {
+                return Err(ProgramParseError::UnknownStateFlag(
+                    line_num,
+                    other.to_owned(),
+                ))
+            }
+        }
+    }
+
+    Ok(State {
+        id,
+        initial,
+        accepting,
+        transitions: Vec::new(),
+    })
+}
+
+/// Parses an `on <pattern> -> <instr> [, <instr> ...] , goto <id>` line.
+fn parse_transition(line: &str, line_num: usize) -> Result<Transition, ProgramParseError> {
+    let without_on = line.trim_start_matches("on").trim_start();
+    let arrow_pos = without_on
+        .find("->")
+        .ok_or(ProgramParseError::MissingArrow(line_num))?;
+    let match_pattern = parse_match_pattern(without_on[..arrow_pos].trim(), line_num)?;
+
+    let after_arrow = &without_on[arrow_pos + "->".len()..];
+    let segments: Vec<&str> = after_arrow.split(',').map(str::trim).collect();
+    let (instr_segments, goto_segment) = match segments.split_last() {
+        Some((goto_segment, instr_segments)) if !instr_segments.is_empty() => {
+            (instr_segments, *goto_segment)
+        }
+        _ => return Err(ProgramParseError::MissingGoto(line_num)),
+    };
+
+    let mut goto_tokens = goto_segment.split_whitespace();
+    let next_state = match (goto_tokens.next(), goto_tokens.next()) {
+        (Some("goto"), Some(id_str)) => id_str
+            .parse()
+            .map_err(|_| ProgramParseError::InvalidStateId(line_num, id_str.to_owned()))?,
+        _ => return Err(ProgramParseError::MissingGoto(line_num)),
+    };
+
+    let tape_instructions = instr_segments
+        .iter()
+        .map(|segment| parse_tape_instruction(segment, line_num))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Transition {
+        match_pattern,
+        tape_instructions,
+        next_state,
+    })
+}
+
+/// Parses `left`, `right`, or `write <char>` into a `TapeInstruction`.
+fn parse_tape_instruction(s: &str, line_num: usize) -> Result<TapeInstruction, ProgramParseError> {
+    match s {
+        "left" => Ok(TapeInstruction::Left),
+        "right" => Ok(TapeInstruction::Right),
+        _ => {
+            let char_str = s.trim_start_matches("write").trim_start();
+            if char_str.len() == s.len() {
+                return Err(ProgramParseError::InvalidTapeInstruction(
+                    line_num,
+                    s.to_owned(),
+                ));
+            }
+            Ok(TapeInstruction::Write(parse_char_literal(
+                char_str, line_num,
+            )?))
+        }
+    }
+}
+
+/// Parses either a bare number (`Char::Num`) or a single quoted character
+/// (`Char::Codepoint`), e.g. `0` or `'f'`.
+fn parse_char_literal(s: &str, line_num: usize) -> Result<Char, ProgramParseError> {
+    if let (Some(inner), true) = (
+        s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')),
+        s.len() >= 3,
+    ) {
+        let mut chars = inner.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => return Ok(Char::Codepoint(c)),
+            _ => return Err(ProgramParseError::InvalidChar(line_num, s.to_owned())),
+        }
+    }
+
+    s.parse()
+        .map(Char::Num)
+        .map_err(|_| ProgramParseError::InvalidChar(line_num, s.to_owned()))
+}
+
+/// Parses a transition's match pattern: `*` (`Wildcard`), a single `<char>`
+/// (`Exact`), or `<char> | <char> | ...` (`AnyOf`).
+fn parse_match_pattern(s: &str, line_num: usize) -> Result<MatchPattern, ProgramParseError> {
+    if s == "*" {
+        return Ok(MatchPattern::Wildcard);
+    }
+
+    let mut chars = s
+        .split('|')
+        .map(|part| parse_char_literal(part.trim(), line_num))
+        .collect::<Result<Vec<_>, _>>()?;
+    if chars.len() == 1 {
+        Ok(MatchPattern::Exact(chars.remove(0)))
+    } else {
+        Ok(MatchPattern::AnyOf(chars))
+    }
+}
+
+/// Errors that can occur while parsing the textual `.tm` program format.
+#[derive(Debug, Fail, PartialEq)]
+pub enum ProgramParseError {
+    #[fail(display = "Line {}: unrecognized line {:?}", 0, 1)]
+    UnrecognizedLine(usize, String),
+    #[fail(display = "Line {}: missing state ID", 0)]
+    MissingStateId(usize),
+    #[fail(display = "Line {}: invalid state ID {:?}", 0, 1)]
+    InvalidStateId(usize, String),
+    #[fail(display = "Line {}: unknown state flag {:?}", 0, 1)]
+    UnknownStateFlag(usize, String),
+    #[fail(display = "Line {}: transition defined before any state", 0)]
+    TransitionBeforeState(usize),
+    #[fail(display = "Line {}: expected '->' after the match character", 0)]
+    MissingArrow(usize),
+    #[fail(display = "Line {}: invalid character literal {:?}", 0, 1)]
+    InvalidChar(usize, String),
+    #[fail(display = "Line {}: invalid tape instruction {:?}", 0, 1)]
+    InvalidTapeInstruction(usize, String),
+    #[fail(display = "Line {}: expected ', goto <id>'", 0)]
+    MissingGoto(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::{Validate, ValidationOptions};
+
+    /// Textual form of the "foo"-matching machine used throughout the crate.
+    const FOO_SRC: &str = "
+        # Matches the string \"foo\"
+        state 1 initial
+        on 'f' -> right, goto 2
+
+        state 2
+        on 'o' -> right, goto 3
+
+        state 3
+        on 'o' -> right, goto 4
+
+        state 4
+        on 0 -> right, goto 5
+
+        state 5 accepting
+    ";
+
+    #[test]
+    fn test_parses_simple_machine() {
+        let program = parse_program(FOO_SRC).unwrap();
+        assert_eq!(program.states.len(), 5);
+        assert_eq!(program.states[0].id, 1);
+        assert!(program.states[0].initial);
+        assert!(!program.states[0].accepting);
+        assert_eq!(program.states[0].transitions.len(), 1);
+        assert_eq!(
+            program.states[0].transitions[0].match_pattern,
+            MatchPattern::Exact(Char::Codepoint('f'))
+        );
+        assert_eq!(
+            program.states[3].transitions[0].match_pattern,
+            MatchPattern::Exact(Char::Num(0))
+        );
+        assert!(program.states[4].accepting);
+    }
+
+    #[test]
+    fn test_parsed_machine_validates() {
+        let program = parse_program(FOO_SRC).unwrap();
+        program
+            .validate_into(&ValidationOptions::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_write_instruction() {
+        let program =
+            parse_program("state 1 initial accepting\non 'a' -> write 'b', goto 1").unwrap();
+        assert_eq!(
+            program.states[0].transitions[0].tape_instructions,
+            vec![TapeInstruction::Write(Char::Codepoint('b'))]
+        );
+    }
+
+    #[test]
+    fn test_multi_instruction_transition() {
+        let program = parse_program(
+            "state 1 initial accepting\non 'a' -> write 'b', right, write 'c', left, goto 1",
+        )
+        .unwrap();
+        assert_eq!(
+            program.states[0].transitions[0].tape_instructions,
+            vec![
+                TapeInstruction::Write(Char::Codepoint('b')),
+                TapeInstruction::Right,
+                TapeInstruction::Write(Char::Codepoint('c')),
+                TapeInstruction::Left,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wildcard_match_pattern() {
+        let program = parse_program("state 1 initial accepting\non * -> right, goto 1").unwrap();
+        assert_eq!(
+            program.states[0].transitions[0].match_pattern,
+            MatchPattern::Wildcard
+        );
+    }
+
+    #[test]
+    fn test_any_of_match_pattern() {
+        let program =
+            parse_program("state 1 initial accepting\non 'a' | 'b' | 0 -> right, goto 1").unwrap();
+        assert_eq!(
+            program.states[0].transitions[0].match_pattern,
+            MatchPattern::AnyOf(vec![
+                Char::Codepoint('a'),
+                Char::Codepoint('b'),
+                Char::Num(0)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_transition_before_state_error() {
+        assert_eq!(
+            parse_program("on 'a' -> right, goto 1"),
+            Err(ProgramParseError::TransitionBeforeState(1))
+        );
+    }
+
+    #[test]
+    fn test_unrecognized_line_error() {
+        assert_eq!(
+            parse_program("this is not a directive"),
+            Err(ProgramParseError::UnrecognizedLine(
+                1,
+                "this is not a directive".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_invalid_char_literal_error() {
+        assert_eq!(
+            parse_program("state 1 initial\non 'ab' -> right, goto 1"),
+            Err(ProgramParseError::InvalidChar(2, "'ab'".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_missing_goto_error() {
+        assert_eq!(
+            parse_program("state 1 initial\non 'a' -> right"),
+            Err(ProgramParseError::MissingGoto(2))
+        );
+    }
+}
@@ -1,5 +1,7 @@
-use crate::stack::SmInstruction;
+use crate::stack::{SmFault, SmInstruction, StackMachine};
+use failure::Fail;
 use itertools::Itertools;
+use std::io::{Read, Write};
 
 trait ToRocketlang {
     fn to_rocketlang(&self) -> String;
@@ -41,3 +43,231 @@ impl ToRocketlang for SmInstruction {
         }
     }
 }
+
+/// Errors that can occur while parsing Rocketlang source back into
+/// `SmInstruction`s.
+#[derive(Debug, Fail, PartialEq)]
+pub enum RocketlangError {
+    #[fail(display = "Line {}: unrecognized phrase {:?}", 0, 1)]
+    UnrecognizedLine(usize, String),
+    #[fail(
+        display = "Line {}: closing phrase {:?} has no matching open block",
+        0, 1
+    )]
+    UnexpectedClose(usize, String),
+    #[fail(
+        display = "Line {}: closing phrase {:?} doesn't match the currently \
+                    open block",
+        0, 1
+    )]
+    MismatchedClose(usize, String),
+    #[fail(display = "{} block(s) left unclosed at end of input", 0)]
+    UnbalancedBlock(usize),
+}
+
+/// The two kinds of nested block that Rocketlang supports, each opened and
+/// closed by a distinct pair of quick-chat phrases.
+#[derive(Debug, PartialEq)]
+enum BlockKind {
+    If,
+    While,
+}
+
+/// Maps a single line of Rocketlang source (trimmed, non-block) to the
+/// `SmInstruction` it represents. This is the inverse of
+/// `SmInstruction::to_rocketlang`.
+fn parse_line(line: &str) -> Option<SmInstruction> {
+    Some(match line {
+        "Take the shot!" => SmInstruction::ReadToActive,
+        "I got it!" => SmInstruction::PrintActive,
+        "Sorry!" => SmInstruction::PrintState,
+        "Wow!" => SmInstruction::IncrActive,
+        "Close one!" => SmInstruction::DecrActive,
+        "Whoops..." => SmInstruction::SaveActive,
+        "OMG!" => SmInstruction::Swap,
+        "Noooo!" => SmInstruction::PushZero,
+        "Defending..." => SmInstruction::PushActive,
+        "Centering..." => SmInstruction::PopToActive,
+        "No Problem." => SmInstruction::ToggleErrors,
+        _ => return None,
+    })
+}
+
+/// Parses Rocketlang quick-chat source into a sequence of `SmInstruction`s,
+/// the inverse of `ToRocketlang`. Nested `If`/`While` blocks are matched by
+/// tracking open blocks on a stack; unbalanced or unrecognized input is
+/// rejected. Blank lines (and surrounding whitespace on each line) are
+/// ignored.
+pub fn from_rocketlang(
+    src: &str,
+) -> Result<Vec<SmInstruction>, RocketlangError> {
+    // `open_blocks` holds, for each currently-open If/While, the kind of
+    // block and the instructions accumulated in its parent scope before it
+    // was opened. `current` accumulates instructions for whichever scope
+    // (top-level or the innermost open block) we're currently in.
+    let mut open_blocks: Vec<(BlockKind, Vec<SmInstruction>)> = Vec::new();
+    let mut current: Vec<SmInstruction> = Vec::new();
+
+    for (i, raw_line) in src.lines().enumerate() {
+        let line_num = i + 1;
+        let line = raw_line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match line {
+            "Nice shot!" => {
+                open_blocks
+                    .push((BlockKind::If, std::mem::take(&mut current)));
+            }
+            "Great pass!" => {
+                open_blocks
+                    .push((BlockKind::While, std::mem::take(&mut current)));
+            }
+            "What a save!" | "Thanks!" => {
+                let (kind, parent) = open_blocks.pop().ok_or_else(|| {
+                    RocketlangError::UnexpectedClose(
+                        line_num,
+                        line.to_owned(),
+                    )
+                })?;
+                let expected = if line == "What a save!" {
+                    BlockKind::If
+                } else {
+                    BlockKind::While
+                };
+                if kind != expected {
+                    return Err(RocketlangError::MismatchedClose(
+                        line_num,
+                        line.to_owned(),
+                    ));
+                }
+                let body = std::mem::replace(&mut current, parent);
+                current.push(if kind == BlockKind::If {
+                    SmInstruction::If(body)
+                } else {
+                    SmInstruction::While(body)
+                });
+            }
+            _ => {
+                let instruction = parse_line(line).ok_or_else(|| {
+                    RocketlangError::UnrecognizedLine(
+                        line_num,
+                        line.to_owned(),
+                    )
+                })?;
+                current.push(instruction);
+            }
+        }
+    }
+
+    if !open_blocks.is_empty() {
+        return Err(RocketlangError::UnbalancedBlock(open_blocks.len()));
+    }
+
+    Ok(current)
+}
+
+/// Parses and runs a Rocketlang source program against the given input/
+/// output, for a full compile-free round-trip of hand-written Rocketlang.
+pub fn execute<R: Read, W: Write>(
+    src: &str,
+    reader: R,
+    writer: &mut W,
+) -> Result<(), RocketlangExecError> {
+    let instructions = from_rocketlang(src)?;
+    let mut machine = StackMachine::new();
+    machine.run(reader, writer, &instructions)?;
+    Ok(())
+}
+
+/// Either stage of `execute` (parsing or running) can fail.
+#[derive(Debug, Fail)]
+pub enum RocketlangExecError {
+    #[fail(display = "{}", 0)]
+    Parse(#[cause] RocketlangError),
+    #[fail(display = "{}", 0)]
+    Fault(#[cause] SmFault),
+}
+
+impl From<RocketlangError> for RocketlangExecError {
+    fn from(error: RocketlangError) -> Self {
+        RocketlangExecError::Parse(error)
+    }
+}
+
+impl From<SmFault> for RocketlangExecError {
+    fn from(fault: SmFault) -> Self {
+        RocketlangExecError::Fault(fault)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_flat() {
+        let instructions =
+            vec![SmInstruction::IncrActive, SmInstruction::Swap];
+        let src = instructions.to_rocketlang();
+        assert_eq!(from_rocketlang(&src).unwrap(), instructions);
+    }
+
+    #[test]
+    fn test_round_trip_nested() {
+        let instructions = vec![SmInstruction::While(vec![
+            SmInstruction::If(vec![SmInstruction::PushZero]),
+            SmInstruction::DecrActive,
+        ])];
+        let src = instructions.to_rocketlang();
+        assert_eq!(from_rocketlang(&src).unwrap(), instructions);
+    }
+
+    #[test]
+    fn test_ignores_blank_lines_and_whitespace() {
+        let src = "  Wow!  \n\n\tOMG!\n";
+        assert_eq!(
+            from_rocketlang(src).unwrap(),
+            vec![SmInstruction::IncrActive, SmInstruction::Swap]
+        );
+    }
+
+    #[test]
+    fn test_unrecognized_line_error() {
+        assert_eq!(
+            from_rocketlang("Not a real phrase!"),
+            Err(RocketlangError::UnrecognizedLine(
+                1,
+                "Not a real phrase!".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_open_block_error() {
+        assert_eq!(
+            from_rocketlang("Nice shot!\nWow!"),
+            Err(RocketlangError::UnbalancedBlock(1))
+        );
+    }
+
+    #[test]
+    fn test_unexpected_close_error() {
+        assert_eq!(
+            from_rocketlang("What a save!"),
+            Err(RocketlangError::UnexpectedClose(
+                1,
+                "What a save!".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_mismatched_close_error() {
+        assert_eq!(
+            from_rocketlang("Nice shot!\nWow!\nThanks!"),
+            Err(RocketlangError::MismatchedClose(3, "Thanks!".to_owned()))
+        );
+    }
+}
@@ -1,15 +1,103 @@
-use serde::Serialize;
+// `StackMachine`/`SmFault` are cfg-gated here so that, on their own, they
+// don't need `std` - everything they touch (`Vec`, `core::fmt`/`core::mem`,
+// a `core_io`-shaped `Read`/`Write`) has a no_std-compatible path behind
+// `#[cfg(not(feature = "std"))]`.
+//
+// IMPORTANT SCOPE NOTE: this does NOT make the crate (or even this module in
+// isolation) buildable as `#![no_std]` today. `lib.rs` never applies
+// `#![cfg_attr(not(feature = "std"), no_std)]`, and every other module
+// (`ast`, `compile`, `compile_native`, `bytecode`, `dsl`, `parser`,
+// `rocketlang`, `termination`, `turing`, `validate`, `utils`, `error`) still
+// unconditionally depends on `std`/`failure`. So turning the `std` feature
+// off would still fail to build the crate, and the `#[cfg(not(feature =
+// "std"))]` branches below are currently unreachable either way. Actually
+// running the rocketlang proof (`TuringMachine`/`rocketlang.rs`) on bare
+// metal would require converting those modules too, which this change does
+// not attempt. Treat this as prep work for `StackMachine`/`SmFault`
+// specifically, not a working no_std build.
+#[cfg(not(feature = "std"))]
+use core::fmt::{self, Display, Formatter};
+#[cfg(not(feature = "std"))]
+use core_io::{self as io, Bytes, Read, Write};
+#[cfg(feature = "std")]
+use failure::Fail;
+#[cfg(feature = "std")]
 use std::{
     fmt::{self, Display, Formatter},
-    io::{self, Bytes, Read, Write},
+    io::{self, BufReader, Bytes, LineWriter, Read, Write},
 };
 
+use serde::{Deserialize, Serialize};
+
+/// A fault raised while executing a compiled program on the [StackMachine].
+/// Unlike a Rust panic, this is a recoverable condition that callers (e.g.
+/// `TuringMachine::run`) can inspect and report, rather than an abort.
+///
+/// Under `std`, this derives `failure::Fail` like every other error type in
+/// the crate; `failure` itself requires `std::error::Error`, so under
+/// `no_std` this instead gets a hand-written `Display` impl and stays a
+/// plain `Debug` enum.
+#[cfg(feature = "std")]
+#[derive(Debug, Fail)]
+pub enum SmFault {
+    /// Attempted to pop the stack while it was empty, with errors enabled
+    /// (see `ToggleErrors`/`errors_enabled`).
+    #[fail(display = "Pop on empty stack")]
+    EmptyStackPop,
+    /// The input reader could not produce any more bytes due to an
+    /// underlying I/O error.
+    #[fail(display = "Input exhausted")]
+    InputExhausted,
+    /// Writing to the output sink failed.
+    #[fail(display = "Output error: {}", 0)]
+    OutputError(io::Error),
+    /// A `Value` computation over/underflowed its integer range.
+    #[fail(display = "Arithmetic overflow")]
+    ArithmeticOverflow,
+    /// The machine executed more primitive instructions than its configured
+    /// step limit allows.
+    #[fail(display = "Exceeded step limit of {}", 0)]
+    StepLimitExceeded(u64),
+    /// A push would grow the stack past its configured maximum depth.
+    #[fail(display = "Exceeded max stack depth of {}", 0)]
+    StackOverflow(usize),
+}
+
+#[cfg(not(feature = "std"))]
+#[derive(Debug)]
+pub enum SmFault {
+    EmptyStackPop,
+    InputExhausted,
+    OutputError(io::Error),
+    ArithmeticOverflow,
+    StepLimitExceeded(u64),
+    StackOverflow(usize),
+}
+
+#[cfg(not(feature = "std"))]
+impl Display for SmFault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SmFault::EmptyStackPop => write!(f, "Pop on empty stack"),
+            SmFault::InputExhausted => write!(f, "Input exhausted"),
+            SmFault::OutputError(e) => write!(f, "Output error: {}", e),
+            SmFault::ArithmeticOverflow => write!(f, "Arithmetic overflow"),
+            SmFault::StepLimitExceeded(limit) => {
+                write!(f, "Exceeded step limit of {}", limit)
+            }
+            SmFault::StackOverflow(max) => {
+                write!(f, "Exceeded max stack depth of {}", max)
+            }
+        }
+    }
+}
+
 /// The size of each register. For tape encoding, we're using 7 bits per char,
 /// so this gives us 9 chars with one extra bit for the sign.
 type Value = i64;
 
 /// One step to run on the stack machine
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum SmInstruction {
     /// Reads one byte from input and sets the active variable to it. If there
     /// is nothing in the input to read, this does nothing.
@@ -158,6 +246,157 @@ pub struct StackMachine {
     inactive_var: Value,
     stack: Vec<Value>,
     errors_enabled: bool,
+    /// Maximum number of primitive instructions to execute before aborting
+    /// with `StepLimitExceeded`. `None` means unbounded.
+    step_limit: Option<u64>,
+    /// Number of primitive instructions executed so far.
+    steps_executed: u64,
+    /// Maximum number of elements allowed on the stack at once. `None`
+    /// means unbounded.
+    max_stack_depth: Option<usize>,
+    /// Tallies per-instruction execution counts, if enabled via
+    /// `with_profiler`. `None` means profiling is off and `run_instruction`
+    /// skips the bookkeeping entirely.
+    profiler: Option<Profiler>,
+}
+
+/// Number of primitive `SmInstruction` variants a [Profiler] tallies
+/// individually. `If`/`While` are excluded: a raw occurrence count for them
+/// is less interesting than how often their bodies actually ran, which
+/// `Profiler::if_taken`/`if_skipped`/`while_iterations` capture instead.
+/// `Comment`/`InlineComment` are excluded too, since they're free (see
+/// `run_instruction`'s step-counting, which skips them the same way) -
+/// `InlineComment`'s wrapped instruction is tallied on its own when
+/// `run_instruction` recurses into it.
+const INSTRUCTION_VARIANT_COUNT: usize = 12;
+
+/// Display names for `Profiler`'s counters, in `variant_index` order.
+const INSTRUCTION_VARIANT_NAMES: [&str; INSTRUCTION_VARIANT_COUNT] = [
+    "ReadToActive",
+    "PrintActive",
+    "PrintState",
+    "IncrActive",
+    "DecrActive",
+    "SaveActive",
+    "Swap",
+    "PushZero",
+    "PushActive",
+    "PopToActive",
+    "ToggleErrors",
+    "DebugPrint",
+];
+
+/// Maps an individually-tallied `SmInstruction` to its slot in `Profiler`'s
+/// counters array. Panics on `If`/`While`/`Comment`/`InlineComment`, which
+/// `Profiler::record` never passes through to this.
+fn variant_index(instruction: &SmInstruction) -> usize {
+    match instruction {
+        SmInstruction::ReadToActive => 0,
+        SmInstruction::PrintActive => 1,
+        SmInstruction::PrintState => 2,
+        SmInstruction::IncrActive => 3,
+        SmInstruction::DecrActive => 4,
+        SmInstruction::SaveActive => 5,
+        SmInstruction::Swap => 6,
+        SmInstruction::PushZero => 7,
+        SmInstruction::PushActive => 8,
+        SmInstruction::PopToActive => 9,
+        SmInstruction::ToggleErrors => 10,
+        SmInstruction::DebugPrint(..) => 11,
+        other => unreachable!("{:?} is tallied separately from variant_index", other),
+    }
+}
+
+/// Tallies how many of each primitive `SmInstruction` a run actually
+/// executes, plus how often `If`/`While` conditions held. The whole crate's
+/// point is to show rocketlang can simulate a Turing machine, so "how
+/// expensive is that simulation" is the natural follow-up question -
+/// `run_instruction`'s own comment about instructions being "proxied to
+/// functions to make it easier to profile" gestures at exactly this.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    counts: [u64; INSTRUCTION_VARIANT_COUNT],
+    if_taken: u64,
+    if_skipped: u64,
+    while_iterations: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tallies one executed instruction. `If`/`While` are handled
+    /// separately by `record_if`/`record_while_iteration`; `Comment`/
+    /// `InlineComment` are free and recorded as nothing (their wrapped
+    /// instruction, for `InlineComment`, is tallied on its own when
+    /// `run_instruction` recurses into it).
+    fn record(&mut self, instruction: &SmInstruction) {
+        match instruction {
+            SmInstruction::Comment(_)
+            | SmInstruction::InlineComment(..)
+            | SmInstruction::If(_)
+            | SmInstruction::While(_) => {}
+            other => self.counts[variant_index(other)] += 1,
+        }
+    }
+
+    fn record_if(&mut self, taken: bool) {
+        if taken {
+            self.if_taken += 1;
+        } else {
+            self.if_skipped += 1;
+        }
+    }
+
+    fn record_while_iteration(&mut self) {
+        self.while_iterations += 1;
+    }
+
+    /// Builds a snapshot of the counts tallied so far.
+    pub fn report(&self) -> ProfileReport {
+        ProfileReport {
+            instruction_counts: INSTRUCTION_VARIANT_NAMES
+                .iter()
+                .zip(&self.counts)
+                .map(|(&name, &count)| (name, count))
+                .collect(),
+            if_taken: self.if_taken,
+            if_skipped: self.if_skipped,
+            while_iterations: self.while_iterations,
+            total: self.counts.iter().sum(),
+        }
+    }
+}
+
+/// A point-in-time readout of a [Profiler]'s counters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileReport {
+    /// How many times each primitive instruction variant ran, in the order
+    /// given by `INSTRUCTION_VARIANT_NAMES`.
+    pub instruction_counts: Vec<(&'static str, u64)>,
+    /// How many times an `If`'s body ran (active == inactive).
+    pub if_taken: u64,
+    /// How many times an `If`'s body was skipped (active != inactive).
+    pub if_skipped: u64,
+    /// How many times a `While`'s body ran for one more iteration.
+    pub while_iterations: u64,
+    /// Total primitive instructions executed, across all variants in
+    /// `instruction_counts`.
+    pub total: u64,
+}
+
+/// A point-in-time capture of a [StackMachine]'s externally-visible state
+/// (everything but the step/depth budget and the step counter, which are
+/// execution config rather than state). Serializable so JSON test vectors
+/// (see `lib/tests/vectors.rs`) can describe an initial/expected-final state
+/// without hand-constructing a `StackMachine`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub active: Value,
+    pub inactive: Value,
+    pub stack: Vec<Value>,
+    pub errors_enabled: bool,
 }
 
 impl StackMachine {
@@ -169,15 +408,89 @@ impl StackMachine {
             inactive_var: 0,
             stack: Vec::new(),
             errors_enabled: true,
+            step_limit: None,
+            steps_executed: 0,
+            max_stack_depth: None,
+            profiler: None,
+        }
+    }
+
+    /// Creates a new machine with an execution budget, to bound
+    /// non-terminating or resource-exhausting programs. `step_limit` caps
+    /// the number of primitive instructions executed; `max_stack_depth`
+    /// caps the number of elements allowed on the stack. Either may be
+    /// `None` to leave that dimension unbounded.
+    pub fn new_with_limits(
+        step_limit: Option<u64>,
+        max_stack_depth: Option<usize>,
+    ) -> Self {
+        Self {
+            step_limit,
+            max_stack_depth,
+            ..Self::new()
+        }
+    }
+
+    /// Returns this machine with profiling turned on, so it tallies
+    /// per-instruction execution counts as it runs; see `report`. Chainable
+    /// so it composes with `new_with_limits`, e.g.
+    /// `StackMachine::new_with_limits(Some(n), None).with_profiler()`.
+    pub fn with_profiler(mut self) -> Self {
+        self.profiler = Some(Profiler::new());
+        self
+    }
+
+    /// The profiling counters tallied so far, if this machine was built via
+    /// `with_profiler`.
+    pub fn report(&self) -> Option<ProfileReport> {
+        self.profiler.as_ref().map(Profiler::report)
+    }
+
+    /// The current value of the active variable.
+    pub fn active_var(&self) -> Value {
+        self.active_var
+    }
+
+    /// The current value of the inactive variable.
+    pub fn inactive_var(&self) -> Value {
+        self.inactive_var
+    }
+
+    /// The current contents of the stack, bottom to top.
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
+
+    /// The number of primitive instructions executed so far.
+    pub fn steps_executed(&self) -> u64 {
+        self.steps_executed
+    }
+
+    /// Builds a machine whose active/inactive vars, stack, and error mode
+    /// match `snapshot`. The step/depth budget is left unbounded, since
+    /// those aren't part of a `Snapshot`.
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        Self {
+            active_var: snapshot.active,
+            inactive_var: snapshot.inactive,
+            stack: snapshot.stack.clone(),
+            errors_enabled: snapshot.errors_enabled,
+            ..Self::new()
         }
     }
 
-    fn error_if_enabled(&self, error: &str) {
-        if self.errors_enabled {
-            panic!("$#@%! ({})", error)
+    /// Captures this machine's current active/inactive vars, stack, and
+    /// error mode as a [Snapshot].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            active: self.active_var,
+            inactive: self.inactive_var,
+            stack: self.stack.clone(),
+            errors_enabled: self.errors_enabled,
         }
     }
 
+    #[cfg(feature = "std")]
     fn write_stack<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         writer.write_all(
             format!(
@@ -194,65 +507,123 @@ impl StackMachine {
         Ok(())
     }
 
-    fn read_to_active<R: Read>(&mut self, reader: &mut Bytes<R>) {
+    /// Same output as the `std` version, but built with `core::fmt::Write`
+    /// directly against `writer` instead of via `format!`, since a bare-
+    /// metal target may have no allocator to build the intermediate
+    /// `String`s on.
+    #[cfg(not(feature = "std"))]
+    fn write_stack<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        use core::fmt::Write as FmtWrite;
+
+        /// Adapts a `core_io::Write` byte sink into a `core::fmt::Write`
+        /// target, so `write!`'s formatting machinery can target it
+        /// directly without an intermediate buffer.
+        struct FmtAdapter<'a, W: Write>(&'a mut W, io::Result<()>);
+        impl<'a, W: Write> FmtWrite for FmtAdapter<'a, W> {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                match self.0.write_all(s.as_bytes()) {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        self.1 = Err(e);
+                        Err(core::fmt::Error)
+                    }
+                }
+            }
+        }
+
+        let mut adapter = FmtAdapter(writer, Ok(()));
+        let format_result = write!(
+            adapter,
+            "Active: {}\nInactive: {}\n-----\n",
+            self.active_var, self.inactive_var,
+        )
+        .and_then(|()| {
+            self.stack
+                .iter()
+                .rev()
+                .try_for_each(|e| write!(adapter, "- {}\n", e))
+        })
+        .and_then(|()| write!(adapter, "\n"));
+
+        match format_result {
+            Ok(()) => Ok(()),
+            // `write_str` already stashed the real I/O error in `adapter.1`;
+            // `core::fmt::Error` itself carries no information.
+            Err(_) => adapter.1,
+        }
+    }
+
+    fn read_to_active<R: Read>(
+        &mut self,
+        reader: &mut Bytes<R>,
+    ) -> Result<(), SmFault> {
         // Read one byte from stdin. If there is nothing to read, do
         // nothing.
         if let Some(res_b) = reader.next() {
-            match res_b {
-                Ok(b) => self.active_var = i64::from(b),
-                Err(error) => {
-                    self.error_if_enabled(&format!("Read error: {}", error));
-                }
-            }
+            let b = res_b.map_err(|_| SmFault::InputExhausted)?;
+            self.active_var = i64::from(b);
         }
+        Ok(())
     }
 
-    fn print_active<W: Write>(&self, writer: &mut W) {
+    fn print_active<W: Write>(&self, writer: &mut W) -> Result<(), SmFault> {
         let to_write = &self.active_var.to_be_bytes()[7..];
-        match writer.write_all(to_write) {
-            Ok(()) => {}
-            Err(error) => {
-                self.error_if_enabled(&format!("Write error: {}", error));
-            }
-        }
+        writer.write_all(to_write).map_err(SmFault::OutputError)
     }
 
-    fn print_state<W: Write>(&self, writer: &mut W) {
-        match self.write_stack(writer) {
-            Ok(()) => {}
-            Err(error) => {
-                self.error_if_enabled(&format!("Write error: {}", error));
-            }
-        }
+    fn print_state<W: Write>(&self, writer: &mut W) -> Result<(), SmFault> {
+        self.write_stack(writer).map_err(SmFault::OutputError)
     }
 
-    fn incr(&mut self) {
-        self.active_var += 1;
+    fn incr(&mut self) -> Result<(), SmFault> {
+        self.active_var = self
+            .active_var
+            .checked_add(1)
+            .ok_or(SmFault::ArithmeticOverflow)?;
+        Ok(())
     }
-    fn decr(&mut self) {
-        self.active_var -= 1;
+    fn decr(&mut self) -> Result<(), SmFault> {
+        self.active_var = self
+            .active_var
+            .checked_sub(1)
+            .ok_or(SmFault::ArithmeticOverflow)?;
+        Ok(())
     }
     fn save_active(&mut self) {
         self.inactive_var = self.active_var;
     }
     fn swap(&mut self) {
-        std::mem::swap(&mut self.active_var, &mut self.inactive_var);
+        core::mem::swap(&mut self.active_var, &mut self.inactive_var);
+    }
+    fn check_stack_depth(&self) -> Result<(), SmFault> {
+        match self.max_stack_depth {
+            Some(max) if self.stack.len() >= max => {
+                Err(SmFault::StackOverflow(max))
+            }
+            _ => Ok(()),
+        }
     }
-    fn push_zero(&mut self) {
+    fn push_zero(&mut self) -> Result<(), SmFault> {
+        self.check_stack_depth()?;
         self.stack.push(0);
+        Ok(())
     }
-    fn push_active(&mut self) {
+    fn push_active(&mut self) -> Result<(), SmFault> {
+        self.check_stack_depth()?;
         self.stack.push(self.active_var);
+        Ok(())
     }
-    fn pop_to_active(&mut self) {
+    fn pop_to_active(&mut self) -> Result<(), SmFault> {
         match self.stack.pop() {
             Some(val) => {
                 self.active_var = val;
+                Ok(())
             }
+            None if self.errors_enabled => Err(SmFault::EmptyStackPop),
             None => {
-                self.error_if_enabled("Pop on empty stack");
-                // If we got here, we know errors are disabled
+                // Errors are disabled, so treat an empty pop as a 0
                 self.active_var = 0;
+                Ok(())
             }
         }
     }
@@ -264,24 +635,33 @@ impl StackMachine {
         reader: &mut Bytes<R>,
         writer: &mut W,
         subinstrs: &[SmInstruction],
-    ) {
-        if self.active_var == self.inactive_var {
+    ) -> Result<(), SmFault> {
+        let taken = self.active_var == self.inactive_var;
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record_if(taken);
+        }
+        if taken {
             for subinstr in subinstrs {
-                self.run_instruction(reader, writer, subinstr)
+                self.run_instruction(reader, writer, subinstr)?;
             }
         }
+        Ok(())
     }
     fn do_while<R: Read, W: Write>(
         &mut self,
         reader: &mut Bytes<R>,
         writer: &mut W,
         subinstrs: &[SmInstruction],
-    ) {
+    ) -> Result<(), SmFault> {
         while self.active_var > 0 {
+            if let Some(profiler) = &mut self.profiler {
+                profiler.record_while_iteration();
+            }
             for subinstr in subinstrs {
-                self.run_instruction(reader, writer, subinstr)
+                self.run_instruction(reader, writer, subinstr)?;
             }
         }
+        Ok(())
     }
 
     /// Runs a single instruction on this machine.
@@ -290,71 +670,309 @@ impl StackMachine {
         reader: &mut Bytes<R>,
         writer: &mut W,
         instruction: &SmInstruction,
-    ) {
+    ) -> Result<(), SmFault> {
+        // Comments and inline comments are free; every other primitive
+        // counts against the step budget, including ones nested inside
+        // `If`/`While` bodies (since this function recurses into those).
+        if !matches!(
+            instruction,
+            SmInstruction::Comment(_) | SmInstruction::InlineComment(..)
+        ) {
+            self.steps_executed += 1;
+            if let Some(limit) = self.step_limit {
+                if self.steps_executed > limit {
+                    return Err(SmFault::StepLimitExceeded(limit));
+                }
+            }
+        }
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(instruction);
+        }
+
         // These are all proxied to functions to make it easier to profile
         match instruction {
-            SmInstruction::ReadToActive => self.read_to_active(reader),
-            SmInstruction::PrintActive => {
-                self.print_active(writer);
-            }
-            SmInstruction::PrintState => {
-                self.print_state(writer);
-            }
-            SmInstruction::IncrActive => {
-                self.incr();
-            }
-            SmInstruction::DecrActive => {
-                self.decr();
-            }
-            SmInstruction::SaveActive => {
-                self.save_active();
-            }
-            SmInstruction::Swap => {
-                self.swap();
-            }
-            SmInstruction::PushZero => {
-                self.push_zero();
-            }
-            SmInstruction::PushActive => {
-                self.push_active();
-            }
-            SmInstruction::PopToActive => {
-                self.pop_to_active();
-            }
-            SmInstruction::ToggleErrors => {
-                self.toggle_errors();
-            }
+            SmInstruction::ReadToActive => self.read_to_active(reader)?,
+            SmInstruction::PrintActive => self.print_active(writer)?,
+            SmInstruction::PrintState => self.print_state(writer)?,
+            SmInstruction::IncrActive => self.incr()?,
+            SmInstruction::DecrActive => self.decr()?,
+            SmInstruction::SaveActive => self.save_active(),
+            SmInstruction::Swap => self.swap(),
+            SmInstruction::PushZero => self.push_zero()?,
+            SmInstruction::PushActive => self.push_active()?,
+            SmInstruction::PopToActive => self.pop_to_active()?,
+            SmInstruction::ToggleErrors => self.toggle_errors(),
             SmInstruction::If(subinstrs) => {
-                self.do_if(reader, writer, subinstrs)
+                self.do_if(reader, writer, subinstrs)?
             }
             SmInstruction::While(subinstrs) => {
-                self.do_while(reader, writer, subinstrs);
+                self.do_while(reader, writer, subinstrs)?
             }
             SmInstruction::Comment(_) => {}
             SmInstruction::InlineComment(subinstr, _) => {
-                self.run_instruction(reader, writer, subinstr)
+                self.run_instruction(reader, writer, subinstr)?
             }
+            // `println!`/`io::stdout` aren't available under `no_std`, and
+            // this instruction is debug-only instrumentation to begin with,
+            // so it's simply a no-op there.
+            #[cfg(feature = "std")]
             SmInstruction::DebugPrint(msg, print_stack) => {
                 println!("[DEBUG] {}", &msg);
                 if *print_stack {
-                    self.write_stack(&mut io::stdout()).unwrap();
+                    self.write_stack(&mut io::stdout())
+                        .map_err(SmFault::OutputError)?;
                 }
             }
+            #[cfg(not(feature = "std"))]
+            SmInstruction::DebugPrint(..) => {}
         }
+        Ok(())
     }
 
     /// Runs all given instructions on this machine, using the given input
-    /// and output.
+    /// and output. Stops and returns the fault as soon as one instruction
+    /// fails.
+    ///
+    /// Under `std`, `reader`/`writer` are buffered internally (see
+    /// `run_with_capacity`), so large tape inputs don't pay a dispatch/
+    /// bounds-check per character the way reading directly off an
+    /// unbuffered `R` would.
+    #[cfg(feature = "std")]
     pub fn run<R: Read, W: Write>(
         &mut self,
         reader: R,
         writer: &mut W,
         instructions: &[SmInstruction],
-    ) {
+    ) -> Result<(), SmFault> {
+        self.run_with_capacity(
+            reader,
+            writer,
+            instructions,
+            DEFAULT_READER_CAPACITY,
+            DEFAULT_WRITER_CAPACITY,
+        )
+    }
+
+    /// Same as `run`, but with explicit capacities for the internal
+    /// `BufReader`/`LineWriter`, for callers that want to tune buffering for
+    /// an especially large (or especially memory-constrained) run.
+    #[cfg(feature = "std")]
+    pub fn run_with_capacity<R: Read, W: Write>(
+        &mut self,
+        reader: R,
+        writer: &mut W,
+        instructions: &[SmInstruction],
+        reader_capacity: usize,
+        writer_capacity: usize,
+    ) -> Result<(), SmFault> {
+        let mut reader_bytes = BufReader::with_capacity(reader_capacity, reader).bytes();
+        // `LineWriter` buffers like `BufWriter` but also auto-flushes on
+        // every `\n`, so `print_state`/`DebugPrint`'s line-oriented dumps
+        // (and the final ACCEPT/REJECT line from `print_string!`) show up
+        // promptly instead of sitting in the buffer until the explicit
+        // flush below.
+        let mut buffered_writer = LineWriter::with_capacity(writer_capacity, writer);
+        let mut result = Ok(());
+        for instruction in instructions {
+            result = self.run_instruction(&mut reader_bytes, &mut buffered_writer, instruction);
+            if result.is_err() {
+                break;
+            }
+        }
+        // Flush unconditionally: a fault can abort the loop above with
+        // output already sitting in `buffered_writer`'s buffer (e.g. a
+        // `PrintActive` with no trailing `\n`), and that output should still
+        // reach the caller instead of being silently dropped. The original
+        // fault takes priority over a flush error, since it's almost always
+        // the more useful of the two to report.
+        let flush_result = buffered_writer.flush().map_err(SmFault::OutputError);
+        result.and(flush_result)
+    }
+
+    /// `no_std` has no `BufReader`/`LineWriter` to lean on, so this runs
+    /// directly against whatever `Read`/`Write` the caller (e.g. a bare-
+    /// metal UART driver) provides.
+    #[cfg(not(feature = "std"))]
+    pub fn run<R: Read, W: Write>(
+        &mut self,
+        reader: R,
+        writer: &mut W,
+        instructions: &[SmInstruction],
+    ) -> Result<(), SmFault> {
         let mut reader_bytes = reader.bytes();
         for instruction in instructions {
-            self.run_instruction(&mut reader_bytes, writer, instruction)
+            self.run_instruction(&mut reader_bytes, writer, instruction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Default capacity for the `BufReader` `StackMachine::run` wraps its input
+/// in. Matches `std::io::BufReader`'s own default.
+#[cfg(feature = "std")]
+pub(crate) const DEFAULT_READER_CAPACITY: usize = 8 * 1024;
+
+/// Default capacity for the `LineWriter` `StackMachine::run` wraps its
+/// output in. Matches `std::io::BufWriter`'s own default.
+#[cfg(feature = "std")]
+pub(crate) const DEFAULT_WRITER_CAPACITY: usize = 8 * 1024;
+
+/// One level of nested execution context for a [Stepper]: either a flat run
+/// of instructions, or the body of a `While`, which gets re-entered from the
+/// top for as long as the loop condition holds.
+struct Frame<'a> {
+    instructions: &'a [SmInstruction],
+    index: usize,
+    is_loop: bool,
+}
+
+/// Whether a call to `Stepper::step` actually advanced the machine.
+#[derive(Debug, PartialEq)]
+pub enum StepOutcome {
+    /// One primitive instruction ran.
+    Ran,
+    /// There was nothing left to execute.
+    Halted,
+}
+
+/// A condition a [Stepper::continue_until] run pauses at, for inspecting the
+/// machine's state (via `StackMachine::snapshot`) mid-program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    /// Pause once `steps_executed()` reaches this value.
+    AtStep(u64),
+    /// Pause as soon as the active and inactive variables are equal.
+    ActiveEqInactive,
+    /// Pause as soon as the active variable equals this value.
+    ActiveEq(Value),
+}
+
+impl Breakpoint {
+    /// Whether this breakpoint's condition holds against `machine`'s
+    /// current state.
+    pub fn is_hit(&self, machine: &StackMachine) -> bool {
+        match self {
+            Breakpoint::AtStep(step) => machine.steps_executed() >= *step,
+            Breakpoint::ActiveEqInactive => {
+                machine.active_var() == machine.inactive_var()
+            }
+            Breakpoint::ActiveEq(target) => machine.active_var() == *target,
+        }
+    }
+}
+
+/// The reason a [Stepper::continue_until] call returned control to the
+/// caller.
+#[derive(Debug, PartialEq)]
+pub enum RunUntil {
+    /// One of the given breakpoints was hit after the most recent step.
+    BreakpointHit,
+    /// The program ran to completion without hitting a breakpoint.
+    Halted,
+}
+
+/// Drives a [StackMachine] through a program one primitive instruction at a
+/// time, instead of running it to completion. This flattens `If`/`While`
+/// bodies onto an explicit stack of frames (rather than the recursion that
+/// `StackMachine::run` uses), so callers (e.g. an interactive debugger) can
+/// pause between any two primitive instructions, including ones nested
+/// inside loops.
+pub struct Stepper<'a> {
+    frames: Vec<Frame<'a>>,
+}
+
+impl<'a> Stepper<'a> {
+    pub fn new(instructions: &'a [SmInstruction]) -> Self {
+        Self {
+            frames: vec![Frame {
+                instructions,
+                index: 0,
+                is_loop: false,
+            }],
+        }
+    }
+
+    /// True once every frame has been exhausted, i.e. the program has
+    /// finished running.
+    pub fn is_halted(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Executes exactly one primitive instruction against `machine`,
+    /// entering or exiting `If`/`While` blocks as needed to find it.
+    /// Returns `StepOutcome::Halted` if the program has already finished.
+    pub fn step<R: Read, W: Write>(
+        &mut self,
+        machine: &mut StackMachine,
+        reader: &mut Bytes<R>,
+        writer: &mut W,
+    ) -> Result<StepOutcome, SmFault> {
+        loop {
+            let frame = match self.frames.last_mut() {
+                Some(frame) => frame,
+                None => return Ok(StepOutcome::Halted),
+            };
+
+            if frame.index >= frame.instructions.len() {
+                if frame.is_loop && machine.active_var > 0 {
+                    frame.index = 0;
+                } else {
+                    self.frames.pop();
+                }
+                continue;
+            }
+
+            let instruction = &frame.instructions[frame.index];
+            frame.index += 1;
+
+            match instruction {
+                SmInstruction::If(body) => {
+                    if machine.active_var == machine.inactive_var {
+                        self.frames.push(Frame {
+                            instructions: body,
+                            index: 0,
+                            is_loop: false,
+                        });
+                    }
+                }
+                SmInstruction::While(body) => {
+                    if machine.active_var > 0 {
+                        self.frames.push(Frame {
+                            instructions: body,
+                            index: 0,
+                            is_loop: true,
+                        });
+                    }
+                }
+                other => {
+                    machine.run_instruction(reader, writer, other)?;
+                    return Ok(StepOutcome::Ran);
+                }
+            }
+        }
+    }
+
+    /// Steps the machine until either it halts or one of `breakpoints` is
+    /// hit, whichever comes first, checking breakpoints after every
+    /// primitive instruction (so a breakpoint nested inside a loop is
+    /// caught on the iteration it first holds, not just the first one).
+    /// Returns [RunUntil::Halted] or [RunUntil::BreakpointHit]; either way,
+    /// `machine.snapshot()` reflects the state at the point execution
+    /// paused.
+    pub fn continue_until<R: Read, W: Write>(
+        &mut self,
+        machine: &mut StackMachine,
+        reader: &mut Bytes<R>,
+        writer: &mut W,
+        breakpoints: &[Breakpoint],
+    ) -> Result<RunUntil, SmFault> {
+        while self.step(machine, reader, writer)? == StepOutcome::Ran {
+            if breakpoints.iter().any(|bp| bp.is_hit(machine)) {
+                return Ok(RunUntil::BreakpointHit);
+            }
         }
+        Ok(RunUntil::Halted)
     }
 }
 
@@ -368,7 +986,7 @@ mod tests {
         instructions: &[SmInstruction],
         input: R,
     ) {
-        sm.run(input, &mut Vec::new(), instructions);
+        sm.run(input, &mut Vec::new(), instructions).unwrap();
     }
 
     fn run_machine(sm: &mut StackMachine, instructions: &[SmInstruction]) {
@@ -437,10 +1055,10 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Pop on empty")]
     fn test_pop_to_active_on_empty_error() {
         let mut sm = StackMachine::new();
-        run_machine(&mut sm, &[PopToActive]);
+        let result = sm.run(io::empty(), &mut Vec::new(), &[PopToActive]);
+        assert!(matches!(result, Err(SmFault::EmptyStackPop)));
     }
 
     #[test]
@@ -449,6 +1067,50 @@ mod tests {
         run_machine(&mut sm, &[ToggleErrors, PopToActive]);
     }
 
+    #[test]
+    fn test_output_before_fault_is_flushed() {
+        // `PrintActive` writes a single byte with no trailing `\n`, so it
+        // sits in the `LineWriter`'s buffer rather than auto-flushing. The
+        // following `PopToActive` then faults on the empty stack - the
+        // write from `PrintActive` must still make it out.
+        let mut sm = StackMachine::new();
+        sm.active_var = b'a' as i64;
+        let mut output = Vec::new();
+        let result = sm.run(io::empty(), &mut output, &[PrintActive, PopToActive]);
+        assert!(matches!(result, Err(SmFault::EmptyStackPop)));
+        assert_eq!(output, b"a");
+    }
+
+    #[test]
+    fn test_step_limit_exceeded() {
+        let mut sm = StackMachine::new_with_limits(Some(2), None);
+        let result = sm.run(
+            io::empty(),
+            &mut Vec::new(),
+            &[IncrActive, IncrActive, IncrActive],
+        );
+        assert!(matches!(result, Err(SmFault::StepLimitExceeded(2))));
+    }
+
+    #[test]
+    fn test_step_limit_counts_nested_instructions() {
+        let mut sm = StackMachine::new_with_limits(Some(2), None);
+        let result = sm.run(
+            io::empty(),
+            &mut Vec::new(),
+            &[IncrActive, While(vec![DecrActive, DecrActive])],
+        );
+        assert!(matches!(result, Err(SmFault::StepLimitExceeded(2))));
+    }
+
+    #[test]
+    fn test_max_stack_depth_exceeded() {
+        let mut sm = StackMachine::new_with_limits(None, Some(1));
+        let result =
+            sm.run(io::empty(), &mut Vec::new(), &[PushZero, PushZero]);
+        assert!(matches!(result, Err(SmFault::StackOverflow(1))));
+    }
+
     #[test]
     fn test_if_positive() {
         let mut sm = StackMachine::new();
@@ -506,4 +1168,106 @@ mod tests {
         assert_eq!(sm.inactive_var, 0);
         assert!(sm.stack.is_empty());
     }
+
+    #[test]
+    fn test_stepper_matches_run() {
+        let instructions = vec![
+            IncrActive,
+            IncrActive,
+            IncrActive,
+            While(vec![PushZero, DecrActive]),
+        ];
+
+        let mut stepped = StackMachine::new();
+        let mut stepper = Stepper::new(&instructions);
+        let mut reader = io::empty().bytes();
+        let mut writer = Vec::new();
+        let mut steps = 0;
+        while stepper.step(&mut stepped, &mut reader, &mut writer).unwrap()
+            == StepOutcome::Ran
+        {
+            steps += 1;
+        }
+        assert!(stepper.is_halted());
+        assert!(steps > 0);
+
+        let mut run_to_completion = StackMachine::new();
+        run_machine(&mut run_to_completion, &instructions);
+
+        assert_eq!(stepped.active_var, run_to_completion.active_var);
+        assert_eq!(stepped.inactive_var, run_to_completion.inactive_var);
+        assert_eq!(stepped.stack, run_to_completion.stack);
+    }
+
+    #[test]
+    fn test_continue_until_breakpoint_hit() {
+        let instructions = vec![IncrActive, IncrActive, IncrActive, IncrActive];
+        let mut sm = StackMachine::new();
+        let mut stepper = Stepper::new(&instructions);
+        let mut reader = io::empty().bytes();
+        let mut writer = Vec::new();
+
+        let run_until = stepper
+            .continue_until(
+                &mut sm,
+                &mut reader,
+                &mut writer,
+                &[Breakpoint::AtStep(2)],
+            )
+            .unwrap();
+
+        assert_eq!(run_until, RunUntil::BreakpointHit);
+        assert_eq!(sm.active_var, 2);
+        assert!(!stepper.is_halted());
+    }
+
+    #[test]
+    fn test_continue_until_halted() {
+        let instructions = vec![IncrActive, IncrActive];
+        let mut sm = StackMachine::new();
+        let mut stepper = Stepper::new(&instructions);
+        let mut reader = io::empty().bytes();
+        let mut writer = Vec::new();
+
+        let run_until = stepper
+            .continue_until(&mut sm, &mut reader, &mut writer, &[Breakpoint::AtStep(100)])
+            .unwrap();
+
+        assert_eq!(run_until, RunUntil::Halted);
+        assert_eq!(sm.active_var, 2);
+        assert!(stepper.is_halted());
+    }
+
+    #[test]
+    fn test_profiler_tallies_known_sequence() {
+        let mut sm = StackMachine::new().with_profiler();
+        run_machine(
+            &mut sm,
+            &[
+                IncrActive,
+                IncrActive,
+                If(vec![Swap]),
+                IncrActive,
+                While(vec![PushZero, DecrActive]),
+            ],
+        );
+        let report = sm.report().unwrap();
+
+        let count_of = |name: &str| {
+            report
+                .instruction_counts
+                .iter()
+                .find(|(n, _)| *n == name)
+                .unwrap()
+                .1
+        };
+        assert_eq!(count_of("IncrActive"), 3);
+        assert_eq!(count_of("Swap"), 0);
+        assert_eq!(count_of("PushZero"), 3);
+        assert_eq!(count_of("DecrActive"), 3);
+        assert_eq!(report.if_taken, 0);
+        assert_eq!(report.if_skipped, 1);
+        assert_eq!(report.while_iterations, 3);
+        assert_eq!(report.total, 3 + 0 + 3 + 3);
+    }
 }
@@ -0,0 +1,332 @@
+use crate::{
+    ast::{Char, MatchPattern, Program, State, StateId, TapeInstruction, Transition},
+    validate::Valid,
+};
+use std::collections::HashMap;
+
+/// How many logical TM steps to simulate before giving up and returning
+/// [`Termination::Unknown`]. This bounds the cost of analysis on machines
+/// this approach can't say anything about.
+const MAX_STEPS: usize = 1 << 16;
+
+/// The result of statically trying to prove a machine never halts, without
+/// actually running it to completion (which may never happen).
+///
+/// This can't decide termination in general - that's the halting problem -
+/// so a result of [`Termination::Unknown`] means exactly that: nothing could
+/// be proven either way within the step budget, not that the machine
+/// necessarily halts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Termination {
+    /// The machine's tape, run-length-encoded into symbol blocks around the
+    /// head, returned to the same shape (same state, same block count, same
+    /// symbol per block, head in the same block) as an earlier point in the
+    /// run, with every block's repeat count at least as large as before and
+    /// at least one strictly larger. Since a transition only ever looks at
+    /// the single symbol under the head, replaying from this bigger-or-equal
+    /// shape retraces the exact same sequence of transitions and arrives
+    /// back at the same shape again, strictly bigger still - so the tape can
+    /// only keep growing, and the machine can never reach a state with no
+    /// transition for the head char.
+    ProvenNonHalting { witness: String },
+    /// No halt and no such repeating-growth pattern were found within
+    /// `MAX_STEPS`. Also covers machines that do halt, slowly, or within
+    /// the bound - this analysis only ever proves non-halting; everything
+    /// else (including genuine halting) falls back to `Unknown`.
+    Unknown,
+}
+
+/// One step's worth of Turing-machine configuration: the current state, and
+/// the tape as a sparse map from position to written char (unwritten
+/// positions are implicitly blank), plus the head's position.
+///
+/// This is a much simpler representation than `Compile`'s stack-machine
+/// encoding, since this module works directly off the `ast`, without ever
+/// compiling or running the machine.
+struct Config {
+    state: StateId,
+    tape: HashMap<i64, Char>,
+    head_pos: i64,
+}
+
+impl Config {
+    fn read_head(&self) -> Char {
+        *self.tape.get(&self.head_pos).unwrap_or(&Char::BLANK)
+    }
+}
+
+/// Finds the transition in `state` that applies to `head`, if any: an exact
+/// match (`Exact`/`AnyOf`) wins, falling back to a `Wildcard` transition
+/// (there's at most one per state, per `Validate for State`).
+fn find_transition<'a>(state: &'a State, head: Char) -> Option<&'a Transition> {
+    let mut wildcard = None;
+    for transition in &state.transitions {
+        match &transition.match_pattern {
+            MatchPattern::Wildcard => wildcard = Some(transition),
+            pattern if pattern.exact_chars().contains(&head) => return Some(transition),
+            _ => {}
+        }
+    }
+    wildcard
+}
+
+/// Advances `config` by one logical step, per `transition`'s instructions.
+fn apply(config: &mut Config, transition: &Transition) {
+    for instruction in &transition.tape_instructions {
+        match instruction {
+            TapeInstruction::Write(c) => {
+                config.tape.insert(config.head_pos, *c);
+            }
+            TapeInstruction::Left => config.head_pos -= 1,
+            TapeInstruction::Right => config.head_pos += 1,
+        }
+    }
+    config.state = transition.next_state;
+}
+
+/// The tape around `config`'s head, compressed into `(symbol, repeat count)`
+/// blocks, along with the index of the block the head currently sits in.
+/// Leading/trailing blanks beyond whatever's been written are not included,
+/// since they extend infinitely either way and don't affect a growth
+/// comparison.
+fn block_shape(config: &Config) -> (Vec<(Char, u32)>, usize) {
+    let (lo, hi) = config
+        .tape
+        .keys()
+        .chain(std::iter::once(&config.head_pos))
+        .fold((config.head_pos, config.head_pos), |(lo, hi), &pos| {
+            (lo.min(pos), hi.max(pos))
+        });
+
+    let mut blocks: Vec<(Char, u32)> = Vec::new();
+    let mut head_block_index = 0;
+    for pos in lo..=hi {
+        let c = *config.tape.get(&pos).unwrap_or(&Char::BLANK);
+        match blocks.last_mut() {
+            Some((last_char, count)) if *last_char == c => *count += 1,
+            _ => blocks.push((c, 1)),
+        }
+        if pos == config.head_pos {
+            head_block_index = blocks.len() - 1;
+        }
+    }
+    (blocks, head_block_index)
+}
+
+/// One recorded checkpoint: the step it was taken at, and the machine's
+/// shape at that point.
+struct Checkpoint {
+    step: usize,
+    state: StateId,
+    blocks: Vec<(Char, u32)>,
+    head_block_index: usize,
+}
+
+/// Tries to prove `program` never halts, starting from an all-blank tape.
+///
+/// This implements a bounded approximation of the "shift rule" technique
+/// used to prove non-termination of busy-beaver-style machines: rather than
+/// symbolically discovering the shift rules themselves (a substantial
+/// undertaking on its own), it simulates concretely and checks, at a
+/// doubling sequence of step counts, whether the tape's block shape has
+/// repeated with every block at least as large as before and at least one
+/// strictly larger. That's a sufficient (not necessary) condition for
+/// non-halting, so this can miss real non-halting machines - it just never
+/// gives a false positive.
+pub fn analyze_termination(program: &Valid<Program>) -> Termination {
+    let states_by_id: HashMap<StateId, &State> = program
+        .states
+        .iter()
+        .map(|state| (state.id, state))
+        .collect();
+    let initial_id = program
+        .states
+        .iter()
+        .find(|state| state.initial)
+        .expect("validated program must have an initial state")
+        .id;
+
+    let mut config = Config {
+        state: initial_id,
+        tape: HashMap::new(),
+        head_pos: 0,
+    };
+    let mut checkpoints: Vec<Checkpoint> = Vec::new();
+    let mut next_checkpoint = 1;
+
+    for step in 0..MAX_STEPS {
+        if step == next_checkpoint {
+            let (blocks, head_block_index) = block_shape(&config);
+            if let Some(earlier) = checkpoints.iter().find(|earlier| {
+                earlier.state == config.state
+                    && earlier.head_block_index == head_block_index
+                    && earlier.blocks.len() == blocks.len()
+                    && earlier
+                        .blocks
+                        .iter()
+                        .zip(&blocks)
+                        .all(|((c1, n1), (c2, n2))| c1 == c2 && n2 >= n1)
+                    && earlier
+                        .blocks
+                        .iter()
+                        .zip(&blocks)
+                        .any(|((_, n1), (_, n2))| n2 > n1)
+            }) {
+                return Termination::ProvenNonHalting {
+                    witness: format!(
+                        "state {} repeats at step {} with the same {}-block tape shape seen at \
+                         step {}, no block smaller and at least one larger",
+                        config.state,
+                        step,
+                        blocks.len(),
+                        earlier.step,
+                    ),
+                };
+            }
+            checkpoints.push(Checkpoint {
+                step,
+                state: config.state,
+                blocks,
+                head_block_index,
+            });
+            next_checkpoint *= 2;
+        }
+
+        let state = states_by_id[&config.state];
+        match find_transition(state, config.read_head()) {
+            Some(transition) => apply(&mut config, transition),
+            // No transition for the head char: the machine halts here (this
+            // is the same "no match" fall-through that `Compile for
+            // [Transition]` encodes as ACCEPT/REJECT), so there's nothing
+            // to prove non-halting about.
+            None => return Termination::Unknown,
+        }
+    }
+
+    Termination::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::Alphabet,
+        validate::{Validate, ValidationOptions},
+    };
+
+    fn validate(program: Program) -> Valid<Program> {
+        program
+            .validate_into(&ValidationOptions::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_halting_machine_is_unknown() {
+        let program = validate(Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: true,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Wildcard,
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 2,
+                    }],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        });
+        assert_eq!(analyze_termination(&program), Termination::Unknown);
+    }
+
+    #[test]
+    fn test_single_state_right_forever_is_proven_non_halting() {
+        // Always writes '1' and moves right, looping on itself forever: the
+        // block of '1's grows by one cell each step, a textbook shift-rule
+        // non-halting machine. The state is accepting so it isn't a dead
+        // state; the Wildcard still matches every char, so it never halts.
+        let program = validate(Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![Transition {
+                    match_pattern: MatchPattern::Wildcard,
+                    tape_instructions: vec![
+                        TapeInstruction::Write(Char::Codepoint('1')),
+                        TapeInstruction::Right,
+                    ],
+                    next_state: 1,
+                }],
+            }],
+            alphabet: Alphabet::default(),
+        });
+        match analyze_termination(&program) {
+            Termination::ProvenNonHalting { .. } => {}
+            other => panic!("expected ProvenNonHalting, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bouncing_non_halting_machine_is_proven_non_halting() {
+        // State 1 writes a '1' on a blank and steps right into state 2,
+        // which steps back left onto it; state 1 then moves right past it
+        // and writes the next '1'. Every three steps the machine is back in
+        // the same state and shape, just with the block of '1's one cell
+        // bigger. State 2 is accepting so neither state is a dead state;
+        // both states cover blank and '1', so it never halts.
+        let program = validate(Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![
+                        Transition {
+                            match_pattern: MatchPattern::Exact(Char::BLANK),
+                            tape_instructions: vec![
+                                TapeInstruction::Write(Char::Codepoint('1')),
+                                TapeInstruction::Right,
+                            ],
+                            next_state: 2,
+                        },
+                        Transition {
+                            match_pattern: MatchPattern::Exact(Char::Codepoint('1')),
+                            tape_instructions: vec![TapeInstruction::Right],
+                            next_state: 1,
+                        },
+                    ],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![
+                        Transition {
+                            match_pattern: MatchPattern::Exact(Char::BLANK),
+                            tape_instructions: vec![TapeInstruction::Left],
+                            next_state: 1,
+                        },
+                        Transition {
+                            match_pattern: MatchPattern::Exact(Char::Codepoint('1')),
+                            tape_instructions: vec![TapeInstruction::Left],
+                            next_state: 2,
+                        },
+                    ],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        });
+        match analyze_termination(&program) {
+            Termination::ProvenNonHalting { .. } => {}
+            other => panic!("expected ProvenNonHalting, got {:?}", other),
+        }
+    }
+}
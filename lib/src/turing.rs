@@ -1,24 +1,27 @@
 use crate::{
-    ast::{Program, BLANK_CHAR},
+    ast::{Alphabet, Char, Program, StateId},
     compile::Compile,
     error::RuntimeError,
-    stack::{SmInstruction, StackMachine},
-    validate::Validate,
+    optimize::optimize,
+    stack::{
+        ProfileReport, SmInstruction, StackMachine, DEFAULT_READER_CAPACITY,
+        DEFAULT_WRITER_CAPACITY,
+    },
+    utils::{validate_char, CharLocation},
+    validate::{Validate, ValidationOptions},
 };
-use ascii::AsciiString;
 use failure::Error;
 use serde::Serialize;
 use std::{
     fmt::{self, Display, Formatter},
     io::{self, Write},
-    str::FromStr,
 };
 
 /// A Turing machine built entirely on Rocketlang's stack machine. This proves
 /// that Rocketlang is Turing-complete.
 ///
-/// The alphabet for this is the characters [0, ALPHABET_SIZE), where 0 is the
-/// empty char (and therefore is _not_ valid input).
+/// The alphabet is configurable per-program (see `ast::Alphabet`); 0 is
+/// always reserved as the empty char, and is therefore never valid input.
 ///
 /// This machine should not be exposed externally, because it assumes that the
 /// input states have been validated.
@@ -31,6 +34,14 @@ use std::{
 #[derive(Debug, Serialize)]
 pub struct TuringMachine {
     instructions: Vec<SmInstruction>,
+    alphabet: Alphabet,
+    /// Maximum number of primitive stack-machine instructions to execute
+    /// per `run_with_capacity` call before aborting. `None` means
+    /// unbounded. See `new_with_limits`.
+    step_limit: Option<u64>,
+    /// Maximum number of elements allowed on the underlying stack machine's
+    /// stack at once. `None` means unbounded. See `new_with_limits`.
+    max_stack_depth: Option<usize>,
 }
 
 impl TuringMachine {
@@ -39,35 +50,175 @@ impl TuringMachine {
     /// ensuring that the IDs are sequential, the initial state is in the array,
     /// etc.
     pub fn new(program: Program) -> Result<Self, Error> {
+        Self::new_with_limits(program, None, None)
+    }
+
+    /// Same as `new`, but bounds the underlying `StackMachine` with an
+    /// execution budget (see `StackMachine::new_with_limits`), so a
+    /// malicious or buggy untrusted `.json` machine definition can't run
+    /// forever or exhaust memory. `step_limit` caps the number of stack-
+    /// machine instructions executed; `max_stack_depth` caps the stack
+    /// size. Either may be `None` to leave that dimension unbounded.
+    pub fn new_with_limits(
+        program: Program,
+        step_limit: Option<u64>,
+        max_stack_depth: Option<usize>,
+    ) -> Result<Self, Error> {
+        let alphabet = program.alphabet;
         Ok(Self {
-            instructions: program.validate_into(&())?.compile(),
+            instructions: optimize(
+                program
+                    .validate_into(&ValidationOptions::default())?
+                    .compile(&()),
+            ),
+            alphabet,
+            step_limit,
+            max_stack_depth,
         })
     }
 
     /// Helper function to execute the machine with the given input string and
     /// output destination.
-    fn run_with_io<W: Write>(
+    ///
+    /// Every char in `input` must be in this machine's alphabet (i.e. not the
+    /// reserved empty char, and not too large to represent). The stack
+    /// machine underneath exchanges raw bytes with the outside world, so
+    /// (per `Alphabet`'s docs) this only handles alphabets up to 256 chars
+    /// for now; each char's codepoint is encoded as a single byte.
+    pub fn run_with_io<W: Write>(&self, input: &str, output: &mut W) -> Result<(), Error> {
+        self.run_with_capacity(input, output, DEFAULT_READER_CAPACITY, DEFAULT_WRITER_CAPACITY)
+    }
+
+    /// Same as `run_with_io`, but with explicit capacities for the
+    /// `StackMachine`'s internal `BufReader`/`LineWriter` (see
+    /// `StackMachine::run_with_capacity`), for callers driving especially
+    /// large tape inputs who want to tune buffering themselves.
+    pub fn run_with_capacity<W: Write>(
         &self,
         input: &str,
         output: &mut W,
+        reader_capacity: usize,
+        writer_capacity: usize,
     ) -> Result<(), Error> {
-        let ascii_str = AsciiString::from_str(&input)?;
+        self.run_core(
+            input,
+            output,
+            reader_capacity,
+            writer_capacity,
+            false,
+            |_| {},
+        )?;
+        Ok(())
+    }
 
-        for c in ascii_str.chars() {
-            if *c == BLANK_CHAR {
-                return Err(RuntimeError::BlankCharInInput.into());
-            }
+    /// Validates and encodes a tape input string into the raw bytes that get
+    /// fed to the underlying `StackMachine`.
+    fn encode_input(&self, input: &str) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::with_capacity(input.len());
+        for (position, c) in input.chars().enumerate() {
+            validate_char(
+                c.into(),
+                &self.alphabet,
+                CharLocation::TapePosition(position),
+            )
+            .map_err(RuntimeError::from)?;
+            bytes.push(c as u32 as u8);
         }
+        Ok(bytes)
+    }
 
-        let mut machine = StackMachine::new();
-        machine.run(ascii_str.as_bytes(), output, &self.instructions);
+    /// Runs this machine on the given input, same as `run_with_output`, but
+    /// calls `on_snapshot` with a `TmSnapshot` at the boundary of each main-
+    /// loop iteration, i.e. once per step of the *logical* Turing machine,
+    /// rather than once per stack-machine primitive. This is for building a
+    /// debugger/visualizer on top of the genuine TM configuration (state,
+    /// tape, head), instead of the underlying stack machine's registers.
+    pub fn run_with_trace<W: Write>(
+        &self,
+        input: &str,
+        output: &mut W,
+        on_snapshot: impl FnMut(TmSnapshot),
+    ) -> Result<(), Error> {
+        self.run_core(
+            input,
+            output,
+            DEFAULT_READER_CAPACITY,
+            DEFAULT_WRITER_CAPACITY,
+            false,
+            on_snapshot,
+        )?;
         Ok(())
     }
 
-    /// Executes this machine on the given input. Uses stdout as the output
-    /// stream.
-    pub fn run(&self, input: &str) -> Result<(), Error> {
-        self.run_with_io(input, &mut io::stdout())
+    /// Does the actual work shared by every `run*` variant: feeds `input`
+    /// through the prelude, steps the main loop (calling `on_snapshot` at
+    /// each iteration boundary), then runs the postlude. Returns the value
+    /// `var_a` held right as the main loop exited (`0` for ACCEPT, `-1` for
+    /// REJECT, per the HALT-transition encoding in `Compile for
+    /// Valid<Program>`), plus a profiling report if `profile` is set.
+    fn run_core<W: Write>(
+        &self,
+        input: &str,
+        output: &mut W,
+        reader_capacity: usize,
+        writer_capacity: usize,
+        profile: bool,
+        mut on_snapshot: impl FnMut(TmSnapshot),
+    ) -> Result<(i64, Option<ProfileReport>), Error> {
+        let bytes = self.encode_input(input)?;
+
+        // The main loop is the last top-level While (the prelude's
+        // input-reading loop comes before it); everything before it is the
+        // prelude (read input onto the tape, set the initial state) and
+        // everything after it is the postlude (print ACCEPT/REJECT). See
+        // `Compile for Valid<Program>`.
+        let main_loop_index = self
+            .instructions
+            .iter()
+            .rposition(|instr| matches!(instr, SmInstruction::While(_)))
+            .expect("Compiled program has no main loop");
+        let (prelude, rest) = self.instructions.split_at(main_loop_index);
+        let (main_loop, postlude) = rest.split_first().unwrap();
+        let body = match main_loop {
+            SmInstruction::While(body) => body,
+            _ => unreachable!("found via rposition above"),
+        };
+
+        let mut machine = StackMachine::new_with_limits(self.step_limit, self.max_stack_depth);
+        if profile {
+            machine = machine.with_profiler();
+        }
+        machine
+            .run_with_capacity(&bytes[..], output, prelude, reader_capacity, writer_capacity)
+            .map_err(RuntimeError::from)?;
+
+        // Mirrors `StackMachine::do_while`'s own loop condition.
+        while machine.active_var() > 0 {
+            on_snapshot(decode_snapshot(&machine, &self.alphabet));
+            machine
+                .run_with_capacity(io::empty(), output, body, reader_capacity, writer_capacity)
+                .map_err(RuntimeError::from)?;
+        }
+        let halting_value = machine.active_var();
+
+        machine
+            .run_with_capacity(io::empty(), output, postlude, reader_capacity, writer_capacity)
+            .map_err(RuntimeError::from)?;
+        Ok((halting_value, machine.report()))
+    }
+
+    /// Executes this machine on the given input, writing its output
+    /// ("ACCEPT"/"REJECT") to stdout, and reports why it halted.
+    pub fn run(&self, input: &str) -> Result<Outcome, Error> {
+        let (halting_value, _) = self.run_core(
+            input,
+            &mut io::stdout(),
+            DEFAULT_READER_CAPACITY,
+            DEFAULT_WRITER_CAPACITY,
+            false,
+            |_| {},
+        )?;
+        Ok(Outcome::from_halting_value(halting_value))
     }
 
     /// Executes this machine on the given input. Returns a byte vector that
@@ -77,6 +228,44 @@ impl TuringMachine {
         self.run_with_io(input, &mut output_buffer)?;
         Ok(output_buffer)
     }
+
+    /// Same as `run_with_output`, but also tallies instruction counts as the
+    /// machine runs (see `StackMachine::with_profiler`) - useful for
+    /// comparing how expensive matching one input is against another, since
+    /// demonstrating that rocketlang can simulate a TM naturally raises the
+    /// question of how costly that simulation is.
+    pub fn run_with_output_and_profile(
+        &self,
+        input: &str,
+    ) -> Result<(Vec<u8>, ProfileReport), Error> {
+        let mut output_buffer = Vec::new();
+        let (_, report) = self.run_core(
+            input,
+            &mut output_buffer,
+            DEFAULT_READER_CAPACITY,
+            DEFAULT_WRITER_CAPACITY,
+            true,
+            |_| {},
+        )?;
+        Ok((
+            output_buffer,
+            report.expect("profiler was enabled via run_core's `profile` flag"),
+        ))
+    }
+
+    /// The compiled stack-machine program backing this Turing machine.
+    /// Exposed so callers can build tooling (e.g. a debugger) directly on
+    /// top of the underlying `StackMachine`.
+    pub fn instructions(&self) -> &[SmInstruction] {
+        &self.instructions
+    }
+
+    /// Builds a fresh `StackMachine` honoring this machine's execution
+    /// budget (see `new_with_limits`), for callers (e.g. a debugger) that
+    /// drive `instructions()` directly instead of going through `run_core`.
+    pub fn new_stack_machine(&self) -> StackMachine {
+        StackMachine::new_with_limits(self.step_limit, self.max_stack_depth)
+    }
 }
 
 impl Display for TuringMachine {
@@ -88,27 +277,97 @@ impl Display for TuringMachine {
     }
 }
 
+/// Why a [`TuringMachine`] run halted. The stack machine itself has no
+/// concept of accept/reject; this is decoded from the `var_a` value the main
+/// loop exits with, per the HALT-transition encoding documented on `Compile
+/// for Valid<Program>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Outcome {
+    /// The machine halted in an accepting state.
+    Accepted,
+    /// The machine halted in a non-accepting state.
+    Rejected,
+    /// The machine halted, but not via either of the above encodings. Not
+    /// known to be reachable given the current codegen, but handled rather
+    /// than panicking in case that ever changes.
+    Halted,
+}
+
+impl Outcome {
+    fn from_halting_value(value: i64) -> Self {
+        match value {
+            0 => Outcome::Accepted,
+            -1 => Outcome::Rejected,
+            _ => Outcome::Halted,
+        }
+    }
+}
+
+/// A human-readable snapshot of the genuine Turing-machine configuration,
+/// i.e. the state, head position, and tape contents - as opposed to the
+/// stack machine's registers/stack, which is how this is actually
+/// represented internally.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TmSnapshot {
+    /// The state the machine is about to process this iteration.
+    pub state: StateId,
+    /// The tape to the left of the head, nearest cell first.
+    pub left: Vec<Char>,
+    /// The char currently under the head.
+    pub head: Char,
+    /// The tape to the right of the head, nearest cell first.
+    pub right: Vec<Char>,
+}
+
+/// Reconstructs a `TmSnapshot` from a `StackMachine`'s state, per the
+/// invariants documented on `Compile for Valid<Program>`: at the start of
+/// each main-loop iteration, `var_a` holds the desired state #, and the
+/// stack holds (top to bottom) the left tape - encoded as a base-
+/// `alphabet.size()` integer, least-significant digit nearest the head -
+/// then the head char, then the right tape.
+fn decode_snapshot(machine: &StackMachine, alphabet: &Alphabet) -> TmSnapshot {
+    let stack = machine.stack();
+    // Cells past the end of the written tape aren't on the stack at all
+    // (popping them yields 0, the blank char), so a missing head decodes as
+    // blank.
+    let mut left_encoded = stack.last().copied().unwrap_or(0);
+    let head = Char::Num(if stack.len() >= 2 { stack[stack.len() - 2] as u32 } else { 0 });
+    let right = stack[..stack.len().saturating_sub(2)]
+        .iter()
+        .rev()
+        .map(|&v| Char::Num(v as u32))
+        .collect();
+
+    let size = i64::from(alphabet.size());
+    let mut left = Vec::new();
+    while left_encoded > 0 {
+        left.push(Char::Num((left_encoded % size) as u32));
+        left_encoded /= size;
+    }
+
+    TmSnapshot {
+        state: machine.active_var() as StateId,
+        left,
+        head,
+        right,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        ast::{State, TapeInstruction, Transition},
+        ast::{Char, MatchPattern, State, TapeInstruction, Transition},
         utils::assert_error,
     };
-    use ascii::AsciiChar;
 
-    fn assert_tm(
-        tm: &TuringMachine,
-        input: &str,
-        should_accept: bool,
-    ) -> Result<(), Error> {
+    fn assert_tm(tm: &TuringMachine, input: &str, should_accept: bool) -> Result<(), Error> {
         // We have to reverse the input cause TMing is hard
-        let output =
-            tm.run_with_output(&input.chars().rev().collect::<String>())?;
-        let output_string = AsciiString::from_ascii(output).unwrap();
+        let output = tm.run_with_output(&input.chars().rev().collect::<String>())?;
+        let output_string = String::from_utf8(output).unwrap();
         let expected_output = if should_accept { "ACCEPT" } else { "REJECT" };
         assert!(
-            output_string.trim().as_str().ends_with(expected_output),
+            output_string.trim().ends_with(expected_output),
             "TM returned wrong output. Expected \"{}\", got:\n{}",
             expected_output,
             output_string,
@@ -126,6 +385,7 @@ mod tests {
                 accepting: true,
                 transitions: vec![],
             }],
+            alphabet: Alphabet::default(),
         });
         assert_error("Invalid state ID: 0", tm_result);
     }
@@ -139,13 +399,14 @@ mod tests {
                 accepting: true,
                 transitions: vec![],
             }],
+            alphabet: Alphabet::default(),
         })
         .unwrap();
-        assert_error("Blank char in input", tm.run("\x00"));
+        assert_error("illegal character '\\0' at tape position 0", tm.run("\x00"));
     }
 
     #[test]
-    fn test_non_ascii_in_input_error() {
+    fn test_char_exceeds_alphabet_error() {
         let tm = TuringMachine::new(Program {
             states: vec![State {
                 id: 1,
@@ -153,23 +414,23 @@ mod tests {
                 accepting: true,
                 transitions: vec![],
             }],
+            alphabet: Alphabet::default(),
         })
         .unwrap();
-        assert_error("the byte at index 0 is not ASCII", tm.run("\u{80}"));
+        assert_error("illegal character '\\u{80}' at tape position 0", tm.run("\u{80}"));
     }
 
-    #[test]
-    fn test_simple_machine() -> Result<(), Error> {
-        // Machine matches the string "foo"
-        let tm = TuringMachine::new(Program {
+    /// Machine that matches the string "foo".
+    fn foo_machine() -> TuringMachine {
+        TuringMachine::new(Program {
             states: vec![
                 State {
                     id: 1,
                     initial: true,
                     accepting: false,
                     transitions: vec![Transition {
-                        match_char: AsciiChar::f,
-                        tape_instruction: TapeInstruction::Right,
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('f')),
+                        tape_instructions: vec![TapeInstruction::Right],
                         next_state: 2,
                     }],
                 },
@@ -178,8 +439,8 @@ mod tests {
                     initial: false,
                     accepting: false,
                     transitions: vec![Transition {
-                        match_char: AsciiChar::o,
-                        tape_instruction: TapeInstruction::Right,
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('o')),
+                        tape_instructions: vec![TapeInstruction::Right],
                         next_state: 3,
                     }],
                 },
@@ -188,8 +449,8 @@ mod tests {
                     initial: false,
                     accepting: false,
                     transitions: vec![Transition {
-                        match_char: AsciiChar::o,
-                        tape_instruction: TapeInstruction::Right,
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('o')),
+                        tape_instructions: vec![TapeInstruction::Right],
                         next_state: 4,
                     }],
                 },
@@ -198,8 +459,8 @@ mod tests {
                     initial: false,
                     accepting: false,
                     transitions: vec![Transition {
-                        match_char: AsciiChar::Null,
-                        tape_instruction: TapeInstruction::Right,
+                        match_pattern: MatchPattern::Exact(Char::BLANK),
+                        tape_instructions: vec![TapeInstruction::Right],
                         next_state: 5,
                     }],
                 },
@@ -210,11 +471,317 @@ mod tests {
                     transitions: vec![],
                 },
             ],
+            alphabet: Alphabet::default(),
         })
-        .unwrap();
+        .unwrap()
+    }
 
+    #[test]
+    fn test_simple_machine() -> Result<(), Error> {
+        let tm = foo_machine();
         assert_tm(&tm, "foo", true)?;
         assert_tm(&tm, "food", false)?;
         Ok(())
     }
+
+    /// Machine that matches the single char 'x', whose initial state's ID
+    /// (2) is not the lowest ID in the program (1). Guards against codegen
+    /// hardcoding the start state to ID 1 instead of honoring `initial`.
+    fn non_lowest_id_initial_machine() -> TuringMachine {
+        TuringMachine::new(Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+                State {
+                    id: 2,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('x')),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 1,
+                    }],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_initial_state_not_lowest_id() -> Result<(), Error> {
+        let tm = non_lowest_id_initial_machine();
+        assert_tm(&tm, "x", true)?;
+        assert_tm(&tm, "y", false)?;
+        Ok(())
+    }
+
+    /// Machine with a transition whose tape instructions are a sequence
+    /// (write, move, write) rather than a single L/R/W, to prove the
+    /// instructions run in order within one transition.
+    fn multi_instruction_machine() -> TuringMachine {
+        TuringMachine::new(Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![
+                            TapeInstruction::Write(Char::Codepoint('x')),
+                            TapeInstruction::Right,
+                            TapeInstruction::Write(Char::Codepoint('y')),
+                        ],
+                        next_state: 2,
+                    }],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        })
+        .unwrap()
+    }
+
+    /// Machine with a longer instruction sequence than
+    /// `multi_instruction_machine` (five steps, both directions), to cover
+    /// chains longer than the minimal write/move/write case.
+    fn long_instruction_sequence_machine() -> TuringMachine {
+        TuringMachine::new(Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![
+                            TapeInstruction::Write(Char::Codepoint('e')),
+                            TapeInstruction::Right,
+                            TapeInstruction::Write(Char::Codepoint('e')),
+                            TapeInstruction::Right,
+                            TapeInstruction::Write(Char::Num(0)),
+                        ],
+                        next_state: 2,
+                    }],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_long_instruction_sequence_runs_in_order() -> Result<(), Error> {
+        let tm = long_instruction_sequence_machine();
+
+        let mut snapshots = Vec::new();
+        let mut output = Vec::new();
+        tm.run_with_trace("a", &mut output, |snapshot| snapshots.push(snapshot))?;
+
+        // Matches the right,right-R(e)-R(e)-R(0)-style chains this feature
+        // was added to support: e, e, 0 written left to right, head landing
+        // on the 0.
+        let halting = snapshots.last().unwrap();
+        assert_eq!(
+            halting.left,
+            vec![Char::Num('e' as u32), Char::Num('e' as u32)]
+        );
+        assert_eq!(halting.head, Char::Num(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_instruction_transition_runs_in_order() -> Result<(), Error> {
+        let tm = multi_instruction_machine();
+
+        let mut snapshots = Vec::new();
+        let mut output = Vec::new();
+        tm.run_with_trace("a", &mut output, |snapshot| snapshots.push(snapshot))?;
+
+        // The transition's own snapshot is taken before it runs, so the
+        // effects of its write/right/write sequence show up in the next
+        // (halting) snapshot: the head moved past the written 'x', leaving
+        // it on the left, and landed on the written 'y'.
+        // Snapshots are decoded from the stack machine's raw registers, so
+        // chars always come back as `Char::Num`, regardless of how they were
+        // written.
+        let halting = snapshots.last().unwrap();
+        assert_eq!(halting.left, vec![Char::Num('x' as u32)]);
+        assert_eq!(halting.head, Char::Num('y' as u32));
+
+        Ok(())
+    }
+
+    /// Machine using `AnyOf` and `Wildcard` match patterns: state 1 accepts
+    /// on 'a' or 'b', and falls back to state 2 (rejecting) on anything
+    /// else via a wildcard. State 2's one transition, on 'z', leads to the
+    /// accepting state so that state 2 isn't a dead state; after the
+    /// single-char inputs below the head is on a blank there, so it rejects.
+    fn any_of_and_wildcard_machine() -> TuringMachine {
+        TuringMachine::new(Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![
+                        Transition {
+                            match_pattern: MatchPattern::AnyOf(vec![
+                                Char::Codepoint('a'),
+                                Char::Codepoint('b'),
+                            ]),
+                            tape_instructions: vec![TapeInstruction::Right],
+                            next_state: 3,
+                        },
+                        Transition {
+                            match_pattern: MatchPattern::Wildcard,
+                            tape_instructions: vec![TapeInstruction::Right],
+                            next_state: 2,
+                        },
+                    ],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('z')),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 3,
+                    }],
+                },
+                State {
+                    id: 3,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_any_of_and_wildcard_match_patterns() -> Result<(), Error> {
+        let tm = any_of_and_wildcard_machine();
+        assert_tm(&tm, "a", true)?;
+        assert_tm(&tm, "b", true)?;
+        assert_tm(&tm, "c", false)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_any_of_without_wildcard_falls_through_on_unmatched_char() -> Result<(), Error> {
+        // With no Wildcard to cover the rest of the alphabet, a char that
+        // isn't in the AnyOf set should hit the existing "no transition
+        // matched" fall-through and halt, not match something it shouldn't.
+        // State 1 isn't accepting, so that halt rejects; a wrong match would
+        // move on to the accepting state 2 instead.
+        let tm = TuringMachine::new(Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::AnyOf(vec![
+                            Char::Codepoint('a'),
+                            Char::Codepoint('b'),
+                        ]),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 2,
+                    }],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        })
+        .unwrap();
+
+        assert_eq!(tm.run("c")?, Outcome::Rejected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_reports_outcome() {
+        let tm = foo_machine();
+        assert_eq!(
+            tm.run(&"foo".chars().rev().collect::<String>()).unwrap(),
+            Outcome::Accepted
+        );
+        assert_eq!(
+            tm.run(&"food".chars().rev().collect::<String>()).unwrap(),
+            Outcome::Rejected
+        );
+    }
+
+    #[test]
+    fn test_new_with_limits_bounds_a_runaway_machine() {
+        // A single state that loops back to itself forever on any char
+        // (including blank), i.e. never halts. `new` (no limits) would
+        // spin forever here, so build this one through `new_with_limits`
+        // instead, as a real caller (e.g. `tmcli run --max-steps`) would
+        // for an untrusted machine definition. The state is accepting so it
+        // isn't a dead state; the Wildcard still matches every char, so it
+        // never halts.
+        let program = Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![Transition {
+                    match_pattern: MatchPattern::Wildcard,
+                    tape_instructions: vec![TapeInstruction::Right],
+                    next_state: 1,
+                }],
+            }],
+            alphabet: Alphabet::default(),
+        };
+        let tm = TuringMachine::new_with_limits(program, Some(100), None).unwrap();
+        assert_error("Exceeded step limit of 100", tm.run(""));
+    }
+
+    #[test]
+    fn test_run_with_trace_tracks_tm_state() -> Result<(), Error> {
+        let tm = foo_machine();
+
+        let mut snapshots = Vec::new();
+        let mut output = Vec::new();
+        // "foo" reversed, per the machine's input convention.
+        tm.run_with_trace("oof", &mut output, |snapshot| snapshots.push(snapshot))?;
+
+        let states: Vec<StateId> = snapshots.iter().map(|s| s.state).collect();
+        assert_eq!(states, vec![1, 2, 3, 4, 5]);
+
+        // One snapshot per state visited (states 1-3 consume "foo", state 4
+        // reads the blank after it, then state 5 is the halting snapshot),
+        // with the head tracking the next
+        // unconsumed char until it runs off the end onto a blank.
+        let heads: Vec<u32> = snapshots[..4].iter().map(|s| s.head.to_u32()).collect();
+        assert_eq!(heads, vec!['f' as u32, 'o' as u32, 'o' as u32, 0]);
+
+        Ok(())
+    }
 }
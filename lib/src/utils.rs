@@ -1,9 +1,52 @@
-use crate::{ast::ALPHABET_SIZE, error::CompilerError};
+use crate::{
+    ast::{Alphabet, Char, StateId},
+    error::{CompilerError, CompilerErrorKind},
+};
+use std::fmt::{self, Display, Formatter};
 
-pub fn validate_char(c: char) -> Result<(), CompilerError> {
-    // Cast both to usize to make sure we don't truncate the character
-    if c == '\x00' || c as usize >= ALPHABET_SIZE as usize {
-        Err(CompilerError::IllegalCharacter(c))
+/// Where an out-of-range character was found, for error reporting. This
+/// covers both machine-definition validation (a bad char in some
+/// transition's match pattern or `Write` instruction) and tape validation at
+/// runtime (a bad character in the input string).
+#[derive(Debug, Clone, PartialEq)]
+pub enum CharLocation {
+    /// A char index into a tape input string.
+    TapePosition(usize),
+    /// A char in the match pattern or a `Write` instruction of the transition
+    /// at `transition` (its index within the state) within the state with ID
+    /// `state`.
+    Transition { state: StateId, transition: usize },
+}
+
+impl Display for CharLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TapePosition(position) => {
+                write!(f, "tape position {}", position)
+            }
+            Self::Transition { state, transition } => {
+                write!(f, "state {}, transition {}", state, transition)
+            }
+        }
+    }
+}
+
+/// Validates that `c` is in `alphabet`, i.e. not the reserved empty char and
+/// not too large to be represented. `location` identifies where `c` came
+/// from, for error reporting.
+pub fn validate_char(
+    c: Char,
+    alphabet: &Alphabet,
+    location: CharLocation,
+) -> Result<(), CompilerError> {
+    let value = c.to_u32();
+    if value == 0 || value >= alphabet.size() {
+        Err(CompilerErrorKind::IllegalCharacter {
+            value: c,
+            location,
+            alphabet_size: alphabet.size(),
+        }
+        .into())
     } else {
         Ok(())
     }
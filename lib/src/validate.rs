@@ -1,9 +1,10 @@
 use crate::{
-    ast::{Char, Program, State, StateId, Transition, ALPHABET_SIZE},
-    error::{CompilerError, CompilerErrors},
+    ast::{Alphabet, Char, MatchPattern, Program, State, StateId, TapeInstruction, Transition},
+    error::{CompilerError, CompilerErrorKind, CompilerErrors, PathSegment},
+    utils::{validate_char, CharLocation},
 };
 use failure::Error;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
 use std::iter;
 use std::ops::Deref;
@@ -20,6 +21,17 @@ impl<T: Debug + Sized> Deref for Valid<T> {
     }
 }
 
+/// Options controlling optional, stricter validation behavior that isn't
+/// always desired (e.g. because it'd reject machines that are valid but
+/// incomplete by design). These are threaded down through `Validate::Context`
+/// to whichever level actually needs them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidationOptions {
+    /// If true, flag every non-accepting state that's missing a transition
+    /// for some in-alphabet character, via `CompilerErrorKind::IncompleteState`.
+    pub check_completeness: bool,
+}
+
 /// Defines validation behavior for a type. Some types require contextual data
 /// for validation, such as a list of valid IDs. This trait defines a type
 /// `Context` for that purpose.
@@ -32,10 +44,7 @@ pub trait Validate: Debug + Sized {
 
     /// Validates this object, and if it's valid, moves it into a `Valid`
     /// wrapper to indicate that.
-    fn validate_into(
-        self,
-        context: &Self::Context,
-    ) -> Result<Valid<Self>, Error> {
+    fn validate_into(self, context: &Self::Context) -> Result<Valid<Self>, Error> {
         let errors = self.validate(context);
         if errors.is_empty() {
             Ok(Valid(self))
@@ -46,20 +55,19 @@ pub trait Validate: Debug + Sized {
 }
 
 impl Validate for Program {
-    type Context = ();
+    type Context = ValidationOptions;
 
-    fn validate(&self, _context: &Self::Context) -> Vec<CompilerError> {
+    fn validate(&self, context: &Self::Context) -> Vec<CompilerError> {
         // Collect initial data that we'll need for validation
         let mut state_ids: HashMap<StateId, usize> = HashMap::new();
         for state in &self.states {
             // Count the number of occurrences of each state ID
             let state_id = state.id;
-            state_ids
-                .insert(state_id, state_ids.get(&state_id).unwrap_or(&0) + 1);
+            state_ids.insert(state_id, state_ids.get(&state_id).unwrap_or(&0) + 1);
         }
-        // Context for state validation
-        let state_validation_ctx: (HashSet<StateId>,) =
-            (state_ids.keys().copied().collect(),);
+        // Shared data for state validation; each state additionally gets its
+        // own path (just `[State(id)]`, since this is the root of the walk).
+        let valid_state_ids: HashSet<StateId> = state_ids.keys().copied().collect();
 
         // Most of the error checking is in this block
         let mut errors: Vec<CompilerError> = iter::empty()
@@ -68,13 +76,25 @@ impl Validate for Program {
                 state_ids
                     .iter()
                     .filter(|(_, count)| **count > 1)
-                    .map(|(id, _)| CompilerError::DuplicateStateId(*id)),
+                    .map(|(id, _)| {
+                        CompilerError::at(
+                            CompilerErrorKind::DuplicateStateId(*id),
+                            vec![PathSegment::State(*id)],
+                        )
+                    }),
             )
             // Validate each individual state (this also validates transitions)
             .chain(
                 self.states
                     .iter()
-                    .map(|state| state.validate(&state_validation_ctx))
+                    .map(|state| {
+                        state.validate(&(
+                            valid_state_ids.clone(),
+                            self.alphabet,
+                            context.check_completeness,
+                            vec![PathSegment::State(state.id)],
+                        ))
+                    })
                     .flatten(),
             )
             .collect();
@@ -87,51 +107,269 @@ impl Validate for Program {
             .map(|state| state.id)
             .collect();
         if initial_states.is_empty() {
-            errors.push(CompilerError::NoInitialState);
+            errors.push(CompilerErrorKind::NoInitialState.into());
         } else if initial_states.len() > 1 {
-            errors.push(CompilerError::MultipleInitialStates(initial_states));
+            errors.push(CompilerErrorKind::MultipleInitialStates(initial_states).into());
+        } else {
+            // Reachability only makes sense with a single, unambiguous
+            // initial state to search from.
+            errors.extend(
+                unreachable_states(&self.states, initial_states[0])
+                    .into_iter()
+                    .map(|id| {
+                        CompilerError::at(
+                            CompilerErrorKind::UnreachableState(id),
+                            vec![PathSegment::State(id)],
+                        )
+                    }),
+            );
+        }
+
+        errors.extend(dead_states(&self.states).into_iter().map(|id| {
+            CompilerError::at(
+                CompilerErrorKind::DeadState(id),
+                vec![PathSegment::State(id)],
+            )
+        }));
+
+        // The stack machine's `ReadToActive`/`PrintActive` exchange raw
+        // bytes with the outside world (see `Alphabet`'s doc comment), so
+        // anything wider than 8 bits would silently truncate instead of
+        // erroring if we let it through.
+        if self.alphabet.char_bits > 8 {
+            errors.push(CompilerErrorKind::AlphabetTooWide(self.alphabet.char_bits).into());
         }
 
         errors
     }
 }
 
+/// Finds every state not reachable from `initial_id` by following
+/// `transition.next_state` edges, via a BFS worklist.
+fn unreachable_states(states: &[State], initial_id: StateId) -> Vec<StateId> {
+    let mut visited = HashSet::new();
+    let mut worklist = VecDeque::new();
+    if states.iter().any(|state| state.id == initial_id) {
+        visited.insert(initial_id);
+        worklist.push_back(initial_id);
+    }
+
+    while let Some(id) = worklist.pop_front() {
+        let transitions = states
+            .iter()
+            .find(|state| state.id == id)
+            .into_iter()
+            .flat_map(|state| &state.transitions);
+        for transition in transitions {
+            if visited.insert(transition.next_state) {
+                worklist.push_back(transition.next_state);
+            }
+        }
+    }
+
+    states
+        .iter()
+        .map(|state| state.id)
+        .filter(|id| !visited.contains(id))
+        .collect()
+}
+
+/// Finds every state that can't reach any `accepting` state, via a backward
+/// BFS worklist over the reversed transition graph, seeded from the
+/// accepting states.
+fn dead_states(states: &[State]) -> Vec<StateId> {
+    let mut predecessors: HashMap<StateId, Vec<StateId>> = HashMap::new();
+    for state in states {
+        for transition in &state.transitions {
+            predecessors
+                .entry(transition.next_state)
+                .or_default()
+                .push(state.id);
+        }
+    }
+
+    let mut productive = HashSet::new();
+    let mut worklist = VecDeque::new();
+    for state in states.iter().filter(|state| state.accepting) {
+        if productive.insert(state.id) {
+            worklist.push_back(state.id);
+        }
+    }
+
+    while let Some(id) = worklist.pop_front() {
+        for &pred in predecessors.get(&id).into_iter().flatten() {
+            if productive.insert(pred) {
+                worklist.push_back(pred);
+            }
+        }
+    }
+
+    states
+        .iter()
+        .map(|state| state.id)
+        .filter(|id| !productive.contains(id))
+        .collect()
+}
+
 impl Validate for State {
-    type Context = (HashSet<StateId>,);
+    // The path passed in is this state's own path, i.e. `[State(self.id)]`.
+    type Context = (HashSet<StateId>, Alphabet, bool, Vec<PathSegment>);
 
     fn validate(&self, context: &Self::Context) -> Vec<CompilerError> {
+        let (valid_states, alphabet, check_completeness, path) = context;
         let mut errors = Vec::new();
 
         // Validate this ID
         if self.id == 0 {
-            errors.push(CompilerError::InvalidStateId(self.id));
+            errors.push(CompilerError::at(
+                CompilerErrorKind::InvalidStateId(self.id),
+                path.clone(),
+            ));
         }
 
-        // Validate each transition
+        // Validate each transition, extending this state's path with the
+        // transition's own index so errors can name their exact location.
         errors.extend(
             self.transitions
                 .iter()
-                .map(|transition| transition.validate(context).into_iter())
+                .enumerate()
+                .map(|(index, transition)| {
+                    let mut transition_path = path.clone();
+                    transition_path.push(PathSegment::Transition(index));
+                    transition
+                        .validate(&(
+                            valid_states.clone(),
+                            self.id,
+                            index,
+                            *alphabet,
+                            transition_path,
+                        ))
+                        .into_iter()
+                })
                 .flatten(),
         );
+
+        // A character with more than one outgoing transition makes this
+        // state's behavior ambiguous.
+        let mut seen_chars: HashSet<u32> = HashSet::new();
+        let mut conflicting_chars: HashSet<u32> = HashSet::new();
+        for transition in &self.transitions {
+            for c in transition.match_pattern.exact_chars() {
+                if !seen_chars.insert(c.to_u32()) {
+                    conflicting_chars.insert(c.to_u32());
+                }
+            }
+        }
+        for transition in &self.transitions {
+            for &c in transition.match_pattern.exact_chars() {
+                if conflicting_chars.contains(&c.to_u32()) {
+                    errors.push(CompilerError::at(
+                        CompilerErrorKind::ConflictingTransition {
+                            state: self.id,
+                            match_char: c,
+                        },
+                        path.clone(),
+                    ));
+                }
+            }
+        }
+
+        // A `Wildcard` matches the entire alphabet, so a second one in the
+        // same state would always overlap the first. A `Wildcard` combined
+        // with explicit transitions is fine though (and the usual way to use
+        // one): codegen gives explicit entries priority and only falls back
+        // to the wildcard for everything else.
+        let wildcard_count = self
+            .transitions
+            .iter()
+            .filter(|transition| matches!(transition.match_pattern, MatchPattern::Wildcard))
+            .count();
+        if wildcard_count > 1 {
+            errors.push(CompilerError::at(
+                CompilerErrorKind::DuplicateWildcardTransition(self.id),
+                path.clone(),
+            ));
+        }
+
+        // In completeness mode, a non-accepting state with no transition for
+        // some in-alphabet character is a trap the author probably didn't
+        // intend. A `Wildcard` transition always covers the whole alphabet,
+        // so it trivially satisfies this check.
+        let has_wildcard = wildcard_count > 0;
+        if *check_completeness && !self.accepting && !has_wildcard {
+            let matched_chars: HashSet<u32> = self
+                .transitions
+                .iter()
+                .flat_map(|transition| transition.match_pattern.exact_chars())
+                .map(Char::to_u32)
+                .collect();
+            // 0 is the reserved blank char, which is never valid input, so
+            // it's excluded from the in-alphabet range checked here.
+            errors.extend((1..alphabet.size()).filter_map(|value| {
+                if matched_chars.contains(&value) {
+                    None
+                } else {
+                    Some(CompilerError::at(
+                        CompilerErrorKind::IncompleteState {
+                            state: self.id,
+                            match_char: Char::Num(value),
+                        },
+                        path.clone(),
+                    ))
+                }
+            }));
+        }
+
         errors
     }
 }
 
 impl Validate for Transition {
-    type Context = (HashSet<StateId>,);
+    // The path passed in is this transition's own path, e.g.
+    // `[State(1), Transition(0)]`.
+    type Context = (HashSet<StateId>, StateId, usize, Alphabet, Vec<PathSegment>);
 
     fn validate(&self, context: &Self::Context) -> Vec<CompilerError> {
+        let (valid_states, state_id, transition_index, alphabet, path) = context;
         let mut errors = Vec::new();
-        // Validate the match char
-        let match_char = self.match_char;
-        if match_char == 0 || match_char >= (ALPHABET_SIZE as Char) {
-            errors.push(CompilerError::IllegalCharacter(match_char));
+
+        // Validate every concrete char in the match pattern (a `Wildcard`
+        // has none to check; it matches the whole alphabet by definition).
+        for c in self.match_pattern.exact_chars() {
+            if let Err(error) = validate_char(
+                *c,
+                alphabet,
+                CharLocation::Transition {
+                    state: *state_id,
+                    transition: *transition_index,
+                },
+            ) {
+                errors.push(error.with_path(path.clone()));
+            }
+        }
+
+        // Validate every char written by this transition's instructions
+        for tape_instruction in &self.tape_instructions {
+            if let TapeInstruction::Write(c) = tape_instruction {
+                if let Err(error) = validate_char(
+                    *c,
+                    alphabet,
+                    CharLocation::Transition {
+                        state: *state_id,
+                        transition: *transition_index,
+                    },
+                ) {
+                    errors.push(error.with_path(path.clone()));
+                }
+            }
         }
 
         // Validate the next state ID
-        if !context.0.contains(&self.next_state) {
-            errors.push(CompilerError::UndefinedState(self.next_state));
+        if !valid_states.contains(&self.next_state) {
+            errors.push(CompilerError::at(
+                CompilerErrorKind::UndefinedState(self.next_state),
+                path.clone(),
+            ));
         }
         errors
     }
@@ -140,7 +378,7 @@ impl Validate for Transition {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::TapeInstruction;
+    use crate::ast::Char;
     use crate::utils::assert_error;
 
     #[test]
@@ -152,8 +390,9 @@ mod tests {
                 accepting: true,
                 transitions: vec![],
             }],
+            alphabet: Alphabet::default(),
         }
-        .validate_into(&());
+        .validate_into(&ValidationOptions::default());
         assert_error("Invalid state ID: 0", result);
     }
 
@@ -174,8 +413,9 @@ mod tests {
                     transitions: vec![],
                 },
             ],
+            alphabet: Alphabet::default(),
         }
-        .validate_into(&());
+        .validate_into(&ValidationOptions::default());
         assert_error("State ID defined multiple times: 1", result);
     }
 
@@ -188,8 +428,9 @@ mod tests {
                 accepting: true,
                 transitions: vec![],
             }],
+            alphabet: Alphabet::default(),
         }
-        .validate_into(&());
+        .validate_into(&ValidationOptions::default());
         assert_error("No state marked as initial", result);
     }
 
@@ -210,8 +451,9 @@ mod tests {
                     transitions: vec![],
                 },
             ],
+            alphabet: Alphabet::default(),
         }
-        .validate_into(&());
+        .validate_into(&ValidationOptions::default());
         assert_error("Multiple states marked as initial: [1, 2]", result);
     }
 
@@ -223,13 +465,14 @@ mod tests {
                 initial: false,
                 accepting: true,
                 transitions: vec![Transition {
-                    match_char: 32,
-                    tape_instruction: TapeInstruction::Left,
+                    match_pattern: MatchPattern::Exact(Char::Num(32)),
+                    tape_instructions: vec![TapeInstruction::Left],
                     next_state: 2, // Invalid
                 }],
             }],
+            alphabet: Alphabet::default(),
         }
-        .validate_into(&());
+        .validate_into(&ValidationOptions::default());
         assert_error("Undefined state: 2", result);
     }
 
@@ -241,14 +484,79 @@ mod tests {
                 initial: false,
                 accepting: true,
                 transitions: vec![Transition {
-                    match_char: 0, // Invalid
-                    tape_instruction: TapeInstruction::Left,
+                    match_pattern: MatchPattern::Exact(Char::Num(0)), // Invalid
+                    tape_instructions: vec![TapeInstruction::Left],
                     next_state: 1,
                 }],
             }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error("illegal character 0 at state 1, transition 0", result);
+    }
+
+    #[test]
+    fn test_unreachable_state_error() {
+        let result = Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 2,
+                    }],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+                // Accepting (so it doesn't also trip DeadState), but never
+                // named by any transition, so it's unreachable.
+                State {
+                    id: 3,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
         }
-        .validate_into(&());
-        assert_error("Illegal character: \x00", result);
+        .validate_into(&ValidationOptions::default());
+        assert_error("Unreachable state: 3", result);
+    }
+
+    #[test]
+    fn test_dead_state_error() {
+        let result = Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 2,
+                    }],
+                },
+                // Reachable from the initial state, but a trap with no path
+                // to any accepting state (there are none in this program).
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: false,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error("Dead state (cannot reach an accepting state): 2", result);
     }
 
     #[test]
@@ -259,13 +567,271 @@ mod tests {
                 initial: false,
                 accepting: true,
                 transitions: vec![Transition {
-                    match_char: 0x80, // 128 - Invalid
-                    tape_instruction: TapeInstruction::Left,
+                    match_pattern: MatchPattern::Exact(Char::Num(0x80)), // 128 - Invalid
+                    tape_instructions: vec![TapeInstruction::Left],
+                    next_state: 1,
+                }],
+            }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error("illegal character 128 at state 1, transition 0", result);
+    }
+
+    #[test]
+    fn test_alphabet_too_wide_error() {
+        let result = Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![],
+            }],
+            alphabet: Alphabet { char_bits: 9 },
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error(
+            "Alphabet char_bits 9 exceeds the 8-bit limit",
+            result,
+        );
+    }
+
+    #[test]
+    fn test_conflicting_transition_error() {
+        let result = Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 1,
+                    },
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 1,
+                    },
+                ],
+            }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error(
+            "State 1 has multiple transitions matching character 'a'",
+            result,
+        );
+    }
+
+    #[test]
+    fn test_incomplete_state_error() {
+        // A 1-bit alphabet, so the only in-alphabet character is 1.
+        let result = Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: false,
+                transitions: vec![],
+            }],
+            alphabet: Alphabet { char_bits: 1 },
+        }
+        .validate_into(&ValidationOptions {
+            check_completeness: true,
+        });
+        assert_error("State 1 has no transition for character 1", result);
+    }
+
+    #[test]
+    fn test_incomplete_state_check_is_opt_in() {
+        // State 1 only handles char 1, leaving chars 2 and 3 (also in a
+        // 2-bit alphabet) unhandled. Without completeness checking, that's
+        // still a valid machine.
+        Program {
+            states: vec![
+                State {
+                    id: 1,
+                    initial: true,
+                    accepting: false,
+                    transitions: vec![Transition {
+                        match_pattern: MatchPattern::Exact(Char::Num(1)),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 2,
+                    }],
+                },
+                State {
+                    id: 2,
+                    initial: false,
+                    accepting: true,
+                    transitions: vec![],
+                },
+            ],
+            alphabet: Alphabet { char_bits: 2 },
+        }
+        .validate_into(&ValidationOptions::default())
+        .unwrap();
+    }
+
+    #[test]
+    fn test_error_names_its_location() {
+        let result = Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 1,
+                    },
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('b')),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 1,
+                    },
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('c')),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 99, // Invalid
+                    },
+                ],
+            }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error("in state 1, transition 2: Undefined state: 99", result);
+    }
+
+    #[test]
+    fn test_any_of_char_conflicts_with_exact() {
+        let result = Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![
+                    Transition {
+                        match_pattern: MatchPattern::AnyOf(vec![
+                            Char::Codepoint('a'),
+                            Char::Codepoint('b'),
+                        ]),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 1,
+                    },
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('b')),
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 1,
+                    },
+                ],
+            }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error(
+            "State 1 has multiple transitions matching character 'b'",
+            result,
+        );
+    }
+
+    #[test]
+    fn test_any_of_illegal_char_error() {
+        let result = Program {
+            states: vec![State {
+                id: 1,
+                initial: false,
+                accepting: true,
+                transitions: vec![Transition {
+                    match_pattern: MatchPattern::AnyOf(vec![
+                        Char::Num(1),
+                        Char::Num(0x80), // 128 - Invalid
+                    ]),
+                    tape_instructions: vec![TapeInstruction::Left],
+                    next_state: 1,
+                }],
+            }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error("illegal character 128 at state 1, transition 0", result);
+    }
+
+    #[test]
+    fn test_duplicate_wildcard_error() {
+        let result = Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![
+                    Transition {
+                        match_pattern: MatchPattern::Wildcard,
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 1,
+                    },
+                    Transition {
+                        match_pattern: MatchPattern::Wildcard,
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 1,
+                    },
+                ],
+            }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default());
+        assert_error("State 1 has multiple wildcard transitions", result);
+    }
+
+    #[test]
+    fn test_wildcard_coexists_with_explicit_transition() {
+        // A Wildcard alongside an explicit entry is the intended fallback
+        // pattern, not a conflict.
+        Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: true,
+                transitions: vec![
+                    Transition {
+                        match_pattern: MatchPattern::Exact(Char::Codepoint('a')),
+                        tape_instructions: vec![TapeInstruction::Left],
+                        next_state: 1,
+                    },
+                    Transition {
+                        match_pattern: MatchPattern::Wildcard,
+                        tape_instructions: vec![TapeInstruction::Right],
+                        next_state: 1,
+                    },
+                ],
+            }],
+            alphabet: Alphabet::default(),
+        }
+        .validate_into(&ValidationOptions::default())
+        .unwrap();
+    }
+
+    #[test]
+    fn test_wildcard_satisfies_completeness_check() {
+        // A lone Wildcard transition covers the whole alphabet, so this is
+        // complete even though no char is matched explicitly.
+        Program {
+            states: vec![State {
+                id: 1,
+                initial: true,
+                accepting: false,
+                transitions: vec![Transition {
+                    match_pattern: MatchPattern::Wildcard,
+                    tape_instructions: vec![TapeInstruction::Left],
                     next_state: 1,
                 }],
             }],
+            alphabet: Alphabet::default(),
         }
-        .validate_into(&());
-        assert_error("Illegal character: \u{80}", result);
+        .validate_into(&ValidationOptions {
+            check_completeness: true,
+        })
+        .unwrap();
     }
 }